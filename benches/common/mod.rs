@@ -0,0 +1,21 @@
+use ndarray_linalg::types::c64;
+use predict::concept_symbol::Concept;
+use predict::covariance::Structure;
+use predict::spectrum::Vector;
+use std::collections::HashMap;
+
+/// Returns a pseudo-random vector of the given length, the same for each seed
+pub fn vector(seed: usize, length: usize) -> Vector {
+    (0..length)
+        .map(|i| c64::new(((seed * 31 + i * 17) % 97) as f64, ((seed * 7 + i) % 13) as f64))
+        .collect()
+}
+
+/// Returns the given number of concepts around pseudo-random vectors of the
+/// given length, labeled from 1, and a concept labeled 0 to categorize
+pub fn concepts(size: usize, length: usize) -> (HashMap<usize, Concept>, Concept) {
+    let concepts = (1..=size)
+        .map(|label| (label, Concept::new(label, vector(label, length), 1.0, Structure::Diagonal)))
+        .collect();
+    (concepts, Concept::new(0, vector(0, length), 1.0, Structure::Diagonal))
+}
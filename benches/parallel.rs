@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use predict::categorization::{categorize, nearest};
+use predict::fourier::{stft, to_complex64};
+use predict::loader::load_audio;
+use predict::markov_model::UnigramModel;
+use predict::metric::Distance;
+use rayon::ThreadPoolBuilder;
+
+mod common;
+
+/// Numbers of threads at which to measure the throughput
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+fn bench_stft(c: &mut Criterion) {
+    let audio = load_audio(&"SA1.WAV".to_string()).unwrap();
+    let signal = to_complex64(audio.samples);
+    let mut group = c.benchmark_group("stft");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(signal.len() as u64));
+    for threads in THREADS.iter() {
+        let pool = ThreadPoolBuilder::new().num_threads(*threads).build().unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(threads), threads, |b, _| {
+            b.iter(|| pool.install(|| stft(signal.view(), 256, 128).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_categorize(c: &mut Criterion) {
+    let (concepts, target) = common::concepts(1024, 64);
+    let unigram = UnigramModel::new();
+    let mut group = c.benchmark_group("categorize");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(concepts.len() as u64));
+    for threads in THREADS.iter() {
+        let pool = ThreadPoolBuilder::new().num_threads(*threads).build().unwrap();
+        group.bench_with_input(BenchmarkId::new("hard", threads), threads, |b, _| {
+            b.iter(|| pool.install(|| categorize(&target, &concepts, &unigram, &Distance::Mahalanobis)))
+        });
+        group.bench_with_input(BenchmarkId::new("nearest", threads), threads, |b, _| {
+            b.iter(|| pool.install(|| nearest(&target, &concepts, &Distance::Mahalanobis)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_stft, bench_categorize);
+criterion_main!(benches);
@@ -0,0 +1,95 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use predict::abstraction::{interpolate, transform, Interpolation};
+use predict::categorization::categorize;
+use predict::concept_symbol::Concept;
+use predict::config::Config;
+use predict::covariance::Structure;
+use predict::fourier::{fft, stft, to_complex64};
+use predict::loader::load_audio;
+use predict::markov_model::UnigramModel;
+use predict::metric::Distance;
+use predict::perception::process;
+use predict::reduction::Reduction;
+use predict::spectrum::Vector;
+use predict::transformation::Transformation;
+
+mod common;
+
+use common::vector;
+
+fn bench_fft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fft");
+    for size in [16, 256, 4096].iter() {
+        let signal = vector(1, *size);
+        group.throughput(Throughput::Elements(*size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, _| {
+            b.iter(|| fft(signal.view()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_categorize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("categorize");
+    let unigram = UnigramModel::new();
+    for size in [16, 256, 4096].iter() {
+        let (concepts, target) = common::concepts(*size, 16);
+        group.throughput(Throughput::Elements(*size as u64));
+        for metric in [Distance::Euclidean, Distance::Mahalanobis].iter() {
+            let id = BenchmarkId::new(format!("{:?}", metric), size);
+            group.bench_with_input(id, size, |b, _| {
+                b.iter(|| categorize(&target, &concepts, &unigram, metric))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    for structure in [Structure::Diagonal, Structure::LowRank(4)].iter() {
+        let concept = Concept::new(1, vector(1, 64), 1.0, *structure);
+        let observation = Concept::new(2, vector(2, 64), 1.0, *structure);
+        group.bench_function(format!("{:?}", structure), |b| {
+            b.iter_batched(
+                || (concept.clone(), observation.clone()),
+                |(mut concept, observation)| concept.update(observation),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_abstraction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("abstraction");
+    let trajectory: Vec<(Vector, usize)> = (0..12).map(|i| (vector(i, 64), 1 + i % 3)).collect();
+    for interpolation in [Interpolation::Step, Interpolation::Cubic, Interpolation::Resample].iter() {
+        group.bench_function(format!("interpolate/{:?}", interpolation), |b| {
+            b.iter(|| interpolate(trajectory.clone(), 16, *interpolation))
+        });
+    }
+    let signal = interpolate(trajectory, 16, Interpolation::Step).unwrap();
+    for transformation in [Transformation::Fourier, Transformation::Cosine, Transformation::Daubechies].iter() {
+        group.bench_function(format!("transform/{:?}", transformation), |b| {
+            b.iter(|| transform(signal.clone(), transformation, &Reduction::Full.into()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_process(c: &mut Criterion) {
+    let config = Config::default().unwrap();
+    let audio = load_audio(&"SA1.WAV".to_string()).unwrap();
+    let frames = stft(to_complex64(audio.samples).view(), config.frame_size, config.hop_size).unwrap();
+    let mut group = c.benchmark_group("process");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(frames.len() as u64));
+    group.bench_function("SA1.WAV", |b| {
+        b.iter_batched(|| frames.clone(), |frames| process(&config, frames).unwrap(), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_fft, bench_categorize, bench_update, bench_abstraction, bench_process);
+criterion_main!(benches);
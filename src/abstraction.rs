@@ -1,50 +1,50 @@
 use crate::concept_symbol::Concept;
-use crate::spectrum::{Spectrum, Vector, Signal};
+use crate::spectrum::{Span, Spectrum, Vector, Signal};
 use num::complex::Complex64;
 use std::f64::consts::PI;
 use itertools::{Itertools, Either};
-use ndarray::{Array, Array1, Array2, ArrayView2, Ix1, Ix2, azip};
+use ndarray::{Array, Array1, ArrayView2, Ix2};
 use ndarray_linalg::types::c64;
-use crate::fourier::fft;
+use serde::{Serialize, Deserialize};
+use crate::error::PredictError;
+use crate::reduction::Reducer;
+use crate::transformation::Transform;
 
-/// Returns the spectrum of the given signal
+/// Returns the spectrum of the given signal, without any positions,
+/// which are known only to the dimension that abstracts the trajectory.
+/// Fails if the transform does not accept signals as wide as this one.
 ///
 /// # Arguments
 /// * `signal` - time-domain signal of which to find the frequency-domain spectrum
+/// * `transformation` - transform from the signal to its coefficients
+/// * `reduction` - reduction of the coefficients to the point of the spectrum
 ///
-pub fn transform(signal: Signal) -> Spectrum {
+pub fn transform(signal: Signal, transformation: &dyn Transform, reduction: &Reducer) -> Result<Spectrum, PredictError> {
+    if transformation.width(signal.ncols()).is_none() {
+        return Err(PredictError::Shape(format!("cannot transform samples of {} components", signal.ncols())));
+    }
     let length = signal.len();
-    let spectrum = fourier(signal);
-    let total_length: usize = spectrum.shape().iter().product();
-    let point = spectrum.into_shape(total_length).unwrap();
-    let point = point.into_dimensionality::<Ix1>().unwrap();
+    let spectrum = transformation.forward(&signal);
+    let point = reduction.reduce(&spectrum, transformation);
 //    let point = spectrum.into_iter().flatten().collect();
-    Spectrum { point, length, }
+    Ok(Spectrum { point, length, span: Span::default(), frames: Span::default() })
 }
 
-pub fn fourier(a: Array2<c64>) -> Array2<c64> {
-    let mut arr = Array2::<c64>::zeros(a.raw_dim());
-    azip!((input in a.genrows(), mut output in arr.genrows_mut()) {
-        output.assign(&fft(input))
-    });
-    arr
-//    let b = a.genrows().into_iter().map(|row| fft(row)).collect();
-//    Array2::from_shape_vec(a.raw_dim(), b).unwrap()
-
-//    let n = a.len();
-//    if n == 1 {
-//        a.into_owned()
-//    } else {
-//        let evens = fft(a.slice(s![..;2]));
-//        let odds = fft(a.slice(s![1..;2]));
-//        let mut comb = Array2::default(n);
-//        azip!((index k, &even in &evens, &odd in &odds) {
-//            let change = odd * (SPEED * k as f64 / n as f64);
-//            comb[k] = even + change;
-//            comb[k + n/2 as usize] = even - change;
-//        });
-//        comb
-//    }
+/// Returns the signal of which the spectrum is the transform, e.g. to
+/// generate the trajectory that a concept of the superior level represents
+///
+/// # Arguments
+/// * `spectrum` - spectrum as returned by `transform`
+/// * `shape` - rows and columns of the signal
+/// * `transformation` - transform from the signal to its coefficients
+/// * `reduction` - reduction of the coefficients to the point of the spectrum
+///
+pub fn invert(spectrum: &Spectrum, shape: (usize, usize), transformation: &dyn Transform, reduction: &Reducer) -> Result<Signal, PredictError> {
+    let (rows, columns) = shape;
+    let width = transformation.width(columns)
+        .ok_or_else(|| PredictError::Shape(format!("cannot invert to samples of {} components", columns)))?;
+    let coefficients = reduction.expand(&spectrum.point, (rows, width), transformation)?;
+    Ok(transformation.inverse(&coefficients))
 }
 
 /// Fast fourier transform from time domain to frequency domain
@@ -74,49 +74,269 @@ pub fn fourier(a: Array2<c64>) -> Array2<c64> {
 //    }
 //}
 
-// TODO: Refactor to use Array2 directly
-/// Returns a signal with resolution length that represents the trajectory
+/// Selects how a trajectory is sampled to a signal of fixed length
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Interpolation {
+    /// Each sample takes the vector of the step of the trajectory it falls in
+    #[default]
+    Step,
+    /// Straight lines between the midpoints of consecutive steps
+    Linear,
+    /// Cubic Hermite spline through the midpoints of the steps
+    Cubic,
+    /// Band-limited resampling of the stepwise signal through its spectrum
+    Resample,
+}
+
+/// Returns a signal with resolution rows that represents the trajectory.
+/// Each vector of the trajectory occupies a step as long as its subtended
+/// length (at least 1), and the signal samples the midpoints of resolution
+/// equal divisions of the whole trajectory. Fails unless the trajectory has
+/// at least one vector, all of the same length, and the resolution is positive.
 ///
 /// # Arguments
 /// * `trajectory` - pairs of vectors and their subtended length
-/// * `resolution` - number of elements in the output signal
+/// * `resolution` - number of rows in the output signal
+/// * `interpolation` - method of sampling between the steps
 ///
-pub fn interpolate(trajectory: Vec<(Vector, usize)>, resolution: u16) -> Signal {
-    // Cumulative sum
-    let mut spread: Vec<(Vector, usize)> = Vec::new();
-    let mut i = 0;
-    for (v, l) in trajectory.into_iter() {
-        spread.push((v, i));
-        i += l;
-    }
-    // Spread into indices with max length of resolution
-    let total = spread.last().unwrap().1;
-    spread = spread.into_iter()
-        .map(|(v,l)| (v, l / total * resolution as usize))
+pub fn interpolate(trajectory: Vec<(Vector, usize)>, resolution: u16, interpolation: Interpolation) -> Result<Signal, PredictError> {
+    if resolution == 0 {
+        return Err(PredictError::Configuration("cannot interpolate to no samples".to_string()));
+    }
+    let width = trajectory.first()
+        .ok_or_else(|| PredictError::Shape("cannot interpolate an empty trajectory".to_string()))?
+        .0.len();
+    if let Some((vector, _)) = trajectory.iter().find(|(vector, _)| vector.len() != width) {
+        return Err(PredictError::Shape(format!(
+            "trajectory mixes vectors of {} and {} components", width, vector.len())));
+    }
+
+    // Cumulative sum of the lengths gives the start of each step
+    let mut starts = Vec::with_capacity(trajectory.len());
+    let mut total = 0;
+    for (_, length) in trajectory.iter() {
+        starts.push(total as f64);
+        total += (*length).max(1);
+    }
+    let total = total as f64;
+    let vectors: Vec<Vector> = trajectory.into_iter().map(|(vector, _)| vector).collect();
+    let midpoints: Vec<f64> = starts.iter().enumerate()
+        .map(|(i, start)| (start + starts.get(i + 1).copied().unwrap_or(total)) / 2.0)
+        .collect();
+    let times: Vec<f64> = (0..resolution)
+        .map(|j| (j as f64 + 0.5) * total / resolution as f64)
         .collect();
 
-    // Unfilled indices get value of the previous index => stepwise signal
-    let mut vectors: Vec<Vector> = Vec::new();
-    let mut repeat = spread.first().unwrap().0.clone();
-    for _ in 0..resolution {
-        if let Some((v, _)) = spread.first() {
-            repeat = v.clone();
-            spread.remove(0);
+    let rows: Vec<Vector> = match interpolation {
+        Interpolation::Step => times.iter()
+            .map(|&time| {
+                let step = starts.iter().rposition(|&start| start <= time).unwrap_or(0);
+                vectors[step].clone()
+            })
+            .collect(),
+        Interpolation::Linear => times.iter()
+            .map(|&time| match knots(&midpoints, time) {
+                Knots::Before => vectors[0].clone(),
+                Knots::After => vectors[vectors.len() - 1].clone(),
+                Knots::Between(a, s) => &vectors[a] * c64::new(1.0 - s, 0.0) + &vectors[a + 1] * c64::new(s, 0.0),
+            })
+            .collect(),
+        Interpolation::Cubic => {
+            let tangents: Vec<Vector> = (0..vectors.len())
+                .map(|i| {
+                    let (a, b) = (i.saturating_sub(1), (i + 1).min(vectors.len() - 1));
+                    if a == b {
+                        Vector::zeros(vectors[i].len())
+                    } else {
+                        (&vectors[b] - &vectors[a]) / c64::new(midpoints[b] - midpoints[a], 0.0)
+                    }
+                })
+                .collect();
+            times.iter()
+                .map(|&time| match knots(&midpoints, time) {
+                    Knots::Before => vectors[0].clone(),
+                    Knots::After => vectors[vectors.len() - 1].clone(),
+                    Knots::Between(a, s) => {
+                        let h = midpoints[a + 1] - midpoints[a];
+                        let (s2, s3) = (s * s, s * s * s);
+                        let weight = |w: f64| c64::new(w, 0.0);
+                        &vectors[a] * weight(2.0 * s3 - 3.0 * s2 + 1.0)
+                            + &tangents[a] * weight(h * (s3 - 2.0 * s2 + s))
+                            + &vectors[a + 1] * weight(-2.0 * s3 + 3.0 * s2)
+                            + &tangents[a + 1] * weight(h * (s3 - s2))
+                    }
+                })
+                .collect()
         }
-        vectors.push(repeat.clone());
+        Interpolation::Resample => resample(&vectors, &starts, total, resolution as usize),
+    };
+
+    // One row per sample of the trajectory
+    let mut signal = Signal::zeros((rows.len(), width));
+    for (i, row) in rows.iter().enumerate() {
+        signal.row_mut(i).assign(row);
     }
+    Ok(signal)
+}
 
-    let mut signal = Signal::zeros((vectors.first().unwrap().len(), vectors.len()));
-    for (i, vector) in vectors.iter().enumerate() {
-        signal.row_mut(i).assign(vector);
+/// Position of a time among the knots of an interpolation
+enum Knots {
+    /// Before the first knot
+    Before,
+    /// After the last knot
+    After,
+    /// Between the knot at the index and the next, at the fraction of the way
+    Between(usize, f64),
+}
+
+/// Returns where the time falls among the knots
+///
+/// # Arguments
+/// * `knots` - times of the knots, in increasing order
+/// * `time` - time to locate
+///
+fn knots(knots: &[f64], time: f64) -> Knots {
+    if time <= knots[0] {
+        return Knots::Before;
+    }
+    match knots.iter().position(|&knot| knot >= time) {
+        Some(b) => Knots::Between(b - 1, (time - knots[b - 1]) / (knots[b] - knots[b - 1])),
+        None => Knots::After,
     }
-    signal
+}
+
+/// Returns the samples of the stepwise trajectory, band-limited to the
+/// frequencies representable by both the trajectory and the samples. The
+/// trajectory is taken as one sample per unit of length, of which only the
+/// retained bins of the discrete Fourier transform are found and then
+/// evaluated at the sample times.
+///
+/// # Arguments
+/// * `vectors` - vectors of the steps, in order
+/// * `starts` - start of each step
+/// * `total` - total length of the steps
+/// * `resolution` - number of samples
+///
+fn resample(vectors: &[Vector], starts: &[f64], total: f64, resolution: usize) -> Vec<Vector> {
+    let length = total as usize;
+    let width = vectors[0].len();
+    let band = (length.min(resolution) as isize - 1) / 2;
+    let twiddle = |k: isize, position: f64| c64::new(0.0, 2.0 * PI * k as f64 * position / total).exp();
+    let bins: Vec<(isize, Vector)> = (-band..=band)
+        .map(|k| {
+            let mut bin = Vector::zeros(width);
+            for p in 0..length {
+                let step = starts.iter().rposition(|&start| start <= p as f64).unwrap_or(0);
+                bin = bin + &vectors[step] * twiddle(-k, p as f64);
+            }
+            (k, bin / c64::new(total, 0.0))
+        })
+        .collect();
+    (0..resolution)
+        .map(|j| {
+            let position = (j as f64 + 0.5) * total / resolution as f64 - 0.5;
+            bins.iter().fold(Vector::zeros(width), |sample, (k, bin)| sample + bin * twiddle(*k, position))
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::reduction::Reduction;
+    use crate::transformation::{Pca, Transformation};
+
+    fn trajectory(values: &[(f64, usize)]) -> Vec<(Vector, usize)> {
+        values.iter()
+            .map(|&(value, length)| (Vector::from(vec![c64::new(value, 0.0); 2]), length))
+            .collect()
+    }
+
+    fn column(signal: &Signal) -> Vec<f64> {
+        signal.column(0).iter().map(|x| x.re).collect()
+    }
+
+    fn close(a: &[f64], b: &[f64]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-9)
+    }
+
+    #[test]
+    fn test_invert() {
+        let signal = interpolate(trajectory(&[(1.0, 1), (2.0, 3), (-1.0, 2)]), 8, Interpolation::Linear).unwrap();
+        for transformation in &[Transformation::Fourier, Transformation::Daubechies] {
+            let spectrum = transform(signal.clone(), transformation, &Reduction::Full.into()).unwrap();
+            assert_eq!((spectrum.point.len(), spectrum.length), (16, 16));
+            let inverse = invert(&spectrum, (8, 2), transformation, &Reduction::Full.into()).unwrap();
+            assert!(close(&column(&inverse), &column(&signal)));
+        }
+        // Truncation keeps the coarsest rows of every column
+        let truncate = Reduction::Truncate(2);
+        let spectrum = transform(signal.clone(), &Transformation::Cosine, &truncate.into()).unwrap();
+        assert_eq!((spectrum.point.len(), spectrum.length), (4, 16));
+        assert_eq!(invert(&spectrum, (8, 2), &Transformation::Cosine, &truncate.into()).unwrap().dim(), (8, 2));
+    }
+
     #[test]
-    fn test() {}
+    fn test_step() {
+        // Steps are as long as their lengths, not one sample each
+        let signal = interpolate(trajectory(&[(1.0, 1), (2.0, 3)]), 8, Interpolation::Step).unwrap();
+        assert_eq!(signal.dim(), (8, 2));
+        assert!(close(&column(&signal), &[1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0]));
+        // A longer trajectory is downsampled to exactly the resolution
+        let long: Vec<(f64, usize)> = (0..10).map(|i| (i as f64, 1)).collect();
+        let signal = interpolate(trajectory(&long), 5, Interpolation::Step).unwrap();
+        assert!(close(&column(&signal), &[1.0, 3.0, 5.0, 7.0, 9.0]));
+    }
+
+    #[test]
+    fn test_linear() {
+        let signal = interpolate(trajectory(&[(0.0, 1), (4.0, 1)]), 4, Interpolation::Linear).unwrap();
+        assert!(close(&column(&signal), &[0.0, 1.0, 3.0, 4.0]));
+        let single = interpolate(trajectory(&[(3.0, 0)]), 3, Interpolation::Linear).unwrap();
+        assert!(close(&column(&single), &[3.0, 3.0, 3.0]));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let empty = interpolate(Vec::new(), 4, Interpolation::Step);
+        assert!(matches!(empty, Err(PredictError::Shape(_))));
+        let mut mixed = trajectory(&[(1.0, 1), (2.0, 1)]);
+        mixed.push((Vector::zeros(3), 1));
+        assert!(matches!(interpolate(mixed, 4, Interpolation::Linear), Err(PredictError::Shape(_))));
+        let none = interpolate(trajectory(&[(1.0, 1)]), 0, Interpolation::Step);
+        assert!(matches!(none, Err(PredictError::Configuration(_))));
+        // Projections only accept samples as wide as those they were fitted to
+        let signal = Signal::from_shape_fn((4, 2), |(i, j)| c64::new((i + j) as f64, 0.0));
+        let pca = Transformation::Pca(Pca::fit(std::slice::from_ref(&signal), 1).unwrap());
+        assert!(transform(signal, &pca, &Reduction::Full.into()).is_ok());
+        let wide = Signal::zeros((4, 3));
+        assert!(matches!(transform(wide, &pca, &Reduction::Full.into()), Err(PredictError::Shape(_))));
+    }
+
+    #[test]
+    fn test_cubic() {
+        // A linear trajectory is reproduced exactly between the knots
+        let line: Vec<(f64, usize)> = (0..4).map(|i| (i as f64, 2)).collect();
+        let cubic = interpolate(trajectory(&line), 8, Interpolation::Cubic).unwrap();
+        let linear = interpolate(trajectory(&line), 8, Interpolation::Linear).unwrap();
+        assert!(close(&column(&cubic), &column(&linear)));
+        // The spline passes through the knots
+        let bumpy = interpolate(trajectory(&[(0.0, 1), (5.0, 1), (1.0, 1)]), 3, Interpolation::Cubic).unwrap();
+        assert!(close(&column(&bumpy), &[0.0, 5.0, 1.0]));
+    }
+
+    #[test]
+    fn test_resample() {
+        // A constant trajectory stays constant, and its mean is preserved
+        let constant = interpolate(trajectory(&[(2.0, 3), (2.0, 2)]), 6, Interpolation::Resample).unwrap();
+        assert!(close(&column(&constant), &[2.0; 6]));
+        let signal = interpolate(trajectory(&[(0.0, 4), (8.0, 4)]), 1, Interpolation::Resample).unwrap();
+        assert!(close(&column(&signal), &[4.0]));
+        // A sampled sinusoid below the band limit is reproduced
+        let wave: Vec<(f64, usize)> = (0..8).map(|i| ((PI * i as f64 / 4.0).cos(), 1)).collect();
+        let samples = interpolate(trajectory(&wave), 8, Interpolation::Resample).unwrap();
+        let expected: Vec<f64> = wave.iter().map(|(value, _)| *value).collect();
+        assert!(close(&column(&samples), &expected));
+    }
 }
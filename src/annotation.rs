@@ -0,0 +1,269 @@
+use crate::concept_symbol::Label;
+use crate::dimension::Dimension;
+use crate::error::PredictError;
+use crate::spectrum::Span;
+use serde::{Serialize, Deserialize};
+use std::fmt::Write;
+use std::fs;
+
+/// Interval of time subtended by a symbol, labeled with its concept
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Interval {
+    /// Time in seconds at which the symbol begins
+    pub start: f64,
+    /// Time in seconds at which the symbol ends
+    pub end: f64,
+    /// Label of the concept of the symbol
+    pub label: Label,
+}
+
+/// Intervals of the remembered symbols of one dimension, in order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Tier {
+    /// Level of the dimension
+    pub level: u16,
+    /// Intervals of the symbols, in order
+    pub intervals: Vec<Interval>,
+}
+
+/// Converts the frame positions of symbols to times in the source
+#[derive(Clone, Copy, Debug)]
+pub struct Timing {
+    /// Number of samples of the source per second
+    pub sample_rate: u32,
+    /// Number of samples between the starts of consecutive frames
+    pub hop_size: usize,
+}
+
+impl Timing {
+    /// Returns the time in seconds at which the given frame begins
+    ///
+    /// # Arguments
+    /// * `frame` - position of the frame in the source
+    ///
+    pub fn seconds(&self, frame: usize) -> f64 {
+        (frame * self.hop_size) as f64 / self.sample_rate as f64
+    }
+
+    /// Returns the interval of time from the start of the first frame in the
+    /// span to the start of the frame after it, so that adjacent spans tile
+    ///
+    /// # Arguments
+    /// * `frames` - positions of the frames
+    /// * `label` - label of the interval
+    ///
+    pub fn interval(&self, frames: Span, label: Label) -> Interval {
+        Interval {
+            start: self.seconds(frames.start),
+            end: self.seconds(frames.end),
+            label,
+        }
+    }
+}
+
+/// Format of the annotation files written for the annotators' tools
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// One label track per level, written to `<stem>.level<n>.txt`
+    Audacity,
+    /// One Praat TextGrid with an interval tier per level, `<stem>.TextGrid`
+    TextGrid,
+    /// All tiers as JSON, written to `<stem>.json`
+    Json,
+}
+
+/// Returns a tier of intervals for each dimension, from its remembered symbols
+///
+/// # Arguments
+/// * `dimensions` - dimensions of the memory, from the lowest level up
+/// * `timing` - conversion from frames to seconds
+///
+pub fn tiers(dimensions: &[Dimension], timing: &Timing) -> Vec<Tier> {
+    dimensions.iter()
+        .map(|dimension| Tier {
+            level: dimension.level(),
+            intervals: dimension.symbols()
+                .map(|(_, symbol)| timing.interval(symbol.frames, symbol.label))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Returns the intervals of a tier as an Audacity label track,
+/// one tab-separated `start end label` line per interval
+///
+/// # Arguments
+/// * `tier` - tier to write
+///
+pub fn audacity(tier: &Tier) -> String {
+    let mut track = String::new();
+    for interval in tier.intervals.iter() {
+        writeln!(track, "{:.6}\t{:.6}\t{}", interval.start, interval.end, interval.label).unwrap();
+    }
+    track
+}
+
+/// Returns the tiers as a Praat TextGrid in the long text format. Gaps
+/// between intervals, e.g. where symbols were forgotten, are filled with
+/// unlabeled intervals as Praat requires.
+///
+/// # Arguments
+/// * `tiers` - tiers to write, each becoming an interval tier
+///
+pub fn textgrid(tiers: &[Tier]) -> String {
+    let end = tiers.iter()
+        .flat_map(|tier| tier.intervals.iter().map(|interval| interval.end))
+        .fold(0.0, f64::max);
+    let mut grid = String::new();
+    writeln!(grid, "File type = \"ooTextFile\"").unwrap();
+    writeln!(grid, "Object class = \"TextGrid\"").unwrap();
+    writeln!(grid).unwrap();
+    writeln!(grid, "xmin = 0").unwrap();
+    writeln!(grid, "xmax = {}", end).unwrap();
+    writeln!(grid, "tiers? <exists>").unwrap();
+    writeln!(grid, "size = {}", tiers.len()).unwrap();
+    writeln!(grid, "item []:").unwrap();
+    for (i, tier) in tiers.iter().enumerate() {
+        let intervals = tile(&tier.intervals, end);
+        writeln!(grid, "    item [{}]:", i + 1).unwrap();
+        writeln!(grid, "        class = \"IntervalTier\"").unwrap();
+        writeln!(grid, "        name = \"level {}\"", tier.level).unwrap();
+        writeln!(grid, "        xmin = 0").unwrap();
+        writeln!(grid, "        xmax = {}", end).unwrap();
+        writeln!(grid, "        intervals: size = {}", intervals.len()).unwrap();
+        for (j, (start, stop, text)) in intervals.iter().enumerate() {
+            writeln!(grid, "        intervals [{}]:", j + 1).unwrap();
+            writeln!(grid, "            xmin = {}", start).unwrap();
+            writeln!(grid, "            xmax = {}", stop).unwrap();
+            writeln!(grid, "            text = \"{}\"", text).unwrap();
+        }
+    }
+    grid
+}
+
+/// Returns the intervals covering [0, end) without gaps or overlaps,
+/// with empty text where no interval is labeled
+fn tile(intervals: &[Interval], end: f64) -> Vec<(f64, f64, String)> {
+    let mut tiled = Vec::new();
+    let mut time = 0.0;
+    for interval in intervals.iter() {
+        if interval.start > time {
+            tiled.push((time, interval.start, String::new()));
+        }
+        let start = interval.start.max(time);
+        if interval.end > start {
+            tiled.push((start, interval.end, interval.label.to_string()));
+            time = interval.end;
+        }
+    }
+    if end > time {
+        tiled.push((time, end, String::new()));
+    }
+    tiled
+}
+
+/// Writes the tiers to annotation files in the given format
+///
+/// # Arguments
+/// * `tiers` - tiers to write
+/// * `format` - format of the files
+/// * `stem` - path of the files without their extension
+///
+pub fn export(tiers: &[Tier], format: Format, stem: &str) -> Result<(), PredictError> {
+    match format {
+        Format::Audacity => {
+            for tier in tiers.iter() {
+                fs::write(format!("{}.level{}.txt", stem, tier.level), audacity(tier))?;
+            }
+        }
+        Format::TextGrid => fs::write(format!("{}.TextGrid", stem), textgrid(tiers))?,
+        Format::Json => fs::write(format!("{}.json", stem), serde_json::to_string_pretty(tiers)?)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::perception::process;
+    use crate::segmentation::Segmentation;
+    use ndarray::Array1;
+    use ndarray_linalg::types::c64;
+
+    fn tier(level: u16, intervals: &[(f64, f64, Label)]) -> Tier {
+        Tier {
+            level,
+            intervals: intervals.iter()
+                .map(|&(start, end, label)| Interval { start, end, label })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_timing() {
+        let timing = Timing { sample_rate: 16000, hop_size: 160 };
+        let interval = timing.interval(Span::new(100, 150), 7);
+        assert_eq!(interval, Interval { start: 1.0, end: 1.5, label: 7 });
+    }
+
+    #[test]
+    fn test_tiers() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.max_depth = 2;
+        config.segmenters = vec![Segmentation::FixedLength(2); 2];
+        let signal = (0..6)
+            .map(|i| Array1::from(vec![c64::new(10.0 * i as f64, 0.0); 4]))
+            .collect();
+        let timing = Timing { sample_rate: 16, hop_size: 4 };
+        let tiers = tiers(&process(&config, signal).unwrap(), &timing);
+        assert_eq!(tiers.len(), 2);
+        assert_eq!(tiers[0].intervals.len(), 6);
+        let bounds: Vec<(f64, f64)> = tiers[1].intervals.iter()
+            .map(|interval| (interval.start, interval.end))
+            .collect();
+        assert_eq!(bounds, vec![(0.0, 0.5), (0.5, 1.0)]);
+    }
+
+    #[test]
+    fn test_audacity() {
+        let track = audacity(&tier(0, &[(0.0, 0.5, 3), (0.5, 1.25, 4)]));
+        assert_eq!(track, "0.000000\t0.500000\t3\n0.500000\t1.250000\t4\n");
+    }
+
+    #[test]
+    fn test_textgrid() {
+        let tiers = vec![
+            tier(0, &[(0.0, 0.5, 3), (0.5, 1.0, 4)]),
+            tier(1, &[(0.5, 0.75, 9)]),
+        ];
+        let grid = textgrid(&tiers);
+        assert!(grid.starts_with("File type = \"ooTextFile\"\n"));
+        assert!(grid.contains("size = 2\n"));
+        assert!(grid.contains("name = \"level 1\""));
+        // The second tier is padded before and after its single interval
+        let second = &grid[grid.find("item [2]").unwrap()..];
+        assert!(second.contains("intervals: size = 3\n"));
+        assert!(second.contains("xmin = 0.5\n            xmax = 0.75\n            text = \"9\""));
+        assert!(second.contains("xmin = 0.75\n            xmax = 1\n            text = \"\""));
+    }
+
+    #[test]
+    fn test_export() -> Result<(), PredictError> {
+        let tiers = vec![tier(0, &[(0.0, 0.5, 3)]), tier(1, &[(0.0, 0.5, 5)])];
+        let stem = std::env::temp_dir().join(format!("predict_annotation_{}", std::process::id()));
+        let stem = stem.to_str().unwrap();
+        for format in &[Format::Audacity, Format::TextGrid, Format::Json] {
+            export(&tiers, *format, stem)?;
+        }
+        assert_eq!(fs::read_to_string(format!("{}.level1.txt", stem))?, "0.000000\t0.500000\t5\n");
+        let restored: Vec<Tier> = serde_json::from_str(&fs::read_to_string(format!("{}.json", stem))?)?;
+        assert_eq!(restored, tiers);
+        assert!(fs::read_to_string(format!("{}.TextGrid", stem))?.contains("text = \"3\""));
+        for suffix in &["level0.txt", "level1.txt", "TextGrid", "json"] {
+            fs::remove_file(format!("{}.{}", stem, suffix))?;
+        }
+        Ok(())
+    }
+}
@@ -1,45 +1,244 @@
 use num::complex::Complex64;
 use std::collections::HashMap;
+use std::f64::consts::LN_2;
 use crate::concept_symbol::{Concept, Label};
 use crate::markov_model::UnigramModel;
-use ndarray_linalg::norm::Norm;
+use crate::metric::Metric;
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Selects how a perceived concept is assigned to a category
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Categorizer {
+    /// Binary membership, ties broken by the least frequent category
+    #[default]
+    Hard,
+    /// Most probable category under the posterior, if it is a member
+    Soft,
+}
+
+/// Posterior distribution over the existing categories for a perceived concept
+#[derive(Clone, Debug)]
+pub struct Posterior {
+    /// Probability of each category label, most probable first
+    pub probabilities: Vec<(Label, f64)>,
+    /// Information content of the perceived point, i.e. -log2 p(point).
+    /// Since p is a density, the surprisal may be negative.
+    pub surprisal: f64,
+}
+
+impl Posterior {
+    /// Returns the most probable category, if there are any categories
+    pub fn map(&self) -> Option<Label> {
+        self.probabilities.first().map(|(label, _)| *label)
+    }
+
+    /// Returns the probability of the given category
+    ///
+    /// # Arguments
+    /// * `label` - category of which to find the probability
+    ///
+    pub fn probability(&self, label: &Label) -> f64 {
+        self.probabilities.iter()
+            .find(|(l, _)| l == label)
+            .map_or(0.0, |(_, p)| *p)
+    }
+}
 
 /// Returns the label of the category to which the given concept belongs.
 /// If concept can belong to multiple categories, it is placed in the most rare,
-/// i.e. the one with the highest information content / entropy.
+/// i.e. the one with the highest information content / entropy, or else in
+/// the one with the least label. The categories are scanned in parallel with
+/// the `parallel` feature.
 ///
 /// # Arguments
 /// * `concept` - concept to categorize
 /// * `concepts` - map of categories to compare input concept to
 /// * `unigram` - unigram model to determine tie-breaks
+/// * `metric` - metric deciding membership of a category
 ///
 pub fn categorize(
     concept: &Concept,
     concepts: &HashMap<Label, Concept>,
     unigram: &UnigramModel<Label>,
+    metric: &(dyn Metric + Sync),
 ) -> Label {
-    concepts.iter().map(|(_, concept)| concept)
-        .filter(|category| member(category, concept))
-        .min_by_key(|concept| unigram.count(&concept.label))
+    #[cfg(feature = "parallel")]
+    let categories = concepts.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let categories = concepts.iter();
+    categories.map(|(_, concept)| concept)
+        .filter(|category| member(category, concept, metric))
+        .min_by_key(|concept| (unigram.count(&concept.label), concept.label))
         .unwrap_or(concept)
         .label
 }
 
+/// Returns the label of the category nearest to the given concept under the
+/// metric, whether or not the concept is a member, if there are any categories.
+/// Ties are broken by the least label, and the categories are scanned in
+/// parallel with the `parallel` feature.
+///
+/// # Arguments
+/// * `concept` - concept to categorize
+/// * `concepts` - map of categories to compare input concept to
+/// * `metric` - metric measuring the distance to each category
+///
+pub fn nearest(
+    concept: &Concept,
+    concepts: &HashMap<Label, Concept>,
+    metric: &(dyn Metric + Sync),
+) -> Option<Label> {
+    #[cfg(feature = "parallel")]
+    let categories = concepts.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let categories = concepts.iter();
+    categories.map(|(label, category)| (*label, metric.distance(category, concept)))
+        .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+        .map(|(label, _)| label)
+}
+
+/// Returns the label of the most probable category of the given concept,
+/// or the label of the concept itself if it is not a member of that category,
+/// along with the full posterior over the categories.
+///
+/// # Arguments
+/// * `concept` - concept to categorize
+/// * `concepts` - map of categories to compare input concept to
+/// * `unigram` - unigram model giving the prior of each category
+/// * `metric` - metric deciding membership of a category
+///
+pub fn categorize_soft(
+    concept: &Concept,
+    concepts: &HashMap<Label, Concept>,
+    unigram: &UnigramModel<Label>,
+    metric: &dyn Metric,
+) -> (Label, Posterior) {
+    let posterior = posterior(concept, concepts, unigram);
+    let label = posterior.map()
+        .and_then(|label| concepts.get(&label))
+        .filter(|category| member(category, concept, metric))
+        .map_or(concept.label, |category| category.label);
+    (label, posterior)
+}
+
+/// Returns the posterior over the categories for the given concept.
+/// The likelihood of each category is the Gaussian of its moments,
+/// and its prior is its Laplace-smoothed frequency in the unigram model.
+///
+/// # Arguments
+/// * `concept` - concept of which the centroid is the perceived point
+/// * `concepts` - map of categories to compute the posterior over
+/// * `unigram` - unigram model giving the prior of each category
+///
+pub fn posterior(
+    concept: &Concept,
+    concepts: &HashMap<Label, Concept>,
+    unigram: &UnigramModel<Label>,
+) -> Posterior {
+    let point = &concept.location.centroid;
+    let total = (unigram.total() + concepts.len()) as f64;
+    let joint: Vec<(Label, f64)> = concepts.values()
+        .map(|category| {
+            let prior = (unigram.count(&category.label) + 1) as f64 / total;
+            (category.label, prior.ln() + category.log_likelihood(point))
+        })
+        .collect();
+
+    // Normalize in the log domain, since the densities are tiny or huge
+    let max = joint.iter().map(|(_, j)| *j).fold(f64::NEG_INFINITY, f64::max);
+    if !max.is_finite() {
+        return Posterior { probabilities: Vec::new(), surprisal: f64::INFINITY };
+    }
+    let evidence = max + joint.iter().map(|(_, j)| (j - max).exp()).sum::<f64>().ln();
+    let mut probabilities: Vec<(Label, f64)> = joint.into_iter()
+        .map(|(label, j)| (label, (j - evidence).exp()))
+        .collect();
+    probabilities.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    Posterior { probabilities, surprisal: -evidence / LN_2 }
+}
+
 /// Decides whether a concept is a member of a category
 ///
 /// # Arguments
 /// * `category` - category to determine membership of
 /// * `concept` - concept to determine membership
+/// * `metric` - metric in which the distance and radius are measured
 ///
-fn member(category: &Concept, target: &Concept) -> bool {
-    let distance = (&category.location.centroid - &target.location.centroid).norm();
-    distance <= category.location.radius
+fn member(category: &Concept, target: &Concept, metric: &dyn Metric) -> bool {
+    metric.distance(category, target) <= metric.radius(category)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::covariance::Structure;
+    use crate::metric::Distance;
+    use crate::spectrum::Vector;
+    use ndarray_linalg::types::c64;
+
+    fn concept(label: Label, value: f64) -> Concept {
+        let vector: Vector = (0..4).map(|_| c64::new(value, 0.0)).collect();
+        Concept::new(label, vector, 3.0, Structure::Diagonal)
+    }
+
+    #[test]
+    fn test_posterior() {
+        let mut concepts = HashMap::new();
+        concepts.insert(1, concept(1, 0.0));
+        concepts.insert(2, concept(2, 1.0));
+        let unigram = UnigramModel::new();
+
+        let near = posterior(&concept(0, 0.1), &concepts, &unigram);
+        assert_eq!(near.map(), Some(1));
+        let sum: f64 = near.probabilities.iter().map(|(_, p)| p).sum();
+        assert!((sum - 1.0).abs() < 1e-9);
+
+        let middle = posterior(&concept(0, 0.5), &concepts, &unigram);
+        assert!((middle.probability(&1) - 0.5).abs() < 1e-9);
+        assert!(middle.surprisal > near.surprisal);
+    }
+
+    #[test]
+    fn test_posterior_prior() {
+        let mut concepts = HashMap::new();
+        concepts.insert(1, concept(1, 0.0));
+        concepts.insert(2, concept(2, 1.0));
+        let mut unigram = UnigramModel::new();
+        unigram.increment(&2);
+        unigram.increment(&2);
+
+        let middle = posterior(&concept(0, 0.5), &concepts, &unigram);
+        assert_eq!(middle.map(), Some(2));
+        assert!((middle.probability(&2) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_categorize_soft() {
+        let mut concepts = HashMap::new();
+        concepts.insert(1, concept(1, 0.0));
+        let unigram = UnigramModel::new();
+        let (label, _) = categorize_soft(&concept(7, 0.1), &concepts, &unigram, &Distance::Euclidean);
+        assert_eq!(label, 1);
+        let (label, far) = categorize_soft(&concept(7, 9.0), &concepts, &unigram, &Distance::Euclidean);
+        assert_eq!(label, 7);
+        assert_eq!(far.map(), Some(1));
+
+        let empty = posterior(&concept(7, 0.0), &HashMap::new(), &unigram);
+        assert_eq!(empty.map(), None);
+        assert!(empty.surprisal.is_infinite());
+    }
+
     #[test]
-    fn test() {}
+    fn test_nearest() {
+        let mut concepts = HashMap::new();
+        concepts.insert(1, concept(1, 0.0));
+        concepts.insert(2, concept(2, 10.0));
+        // Far outside every radius, the nearest category is still found
+        assert_eq!(nearest(&concept(7, 40.0), &concepts, &Distance::Euclidean), Some(2));
+        assert_eq!(nearest(&concept(7, 1.0), &concepts, &Distance::Euclidean), Some(1));
+        assert_eq!(nearest(&concept(7, 1.0), &HashMap::new(), &Distance::Euclidean), None);
+    }
 }
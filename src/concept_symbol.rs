@@ -1,59 +1,209 @@
-use crate::spectrum::{Spectrum, Vector};
-use num::complex::Complex64;
-use rand;
-use rand::Rng;
+use crate::spectrum::{Span, Spectrum, Vector};
+use crate::covariance::{append, quantile, row_energy, truncate, Covariance, Structure};
 use std::hash::{Hash, Hasher};
 use serde::{Serialize, Deserialize};
+use ndarray::{Array1, Array2};
 use ndarray_linalg::types::c64;
-use ndarray_linalg::norm::Norm;
-use approx::AbsDiff;
-use itertools::Itertools;
+use std::f64::consts::PI;
 
-/// Generates a label, concept, and symbol from spectrum
+/// Smallest variance of a component, so that the covariance is invertible
+const MIN_VARIANCE: f64 = 1e-12;
+
+/// Generates a concept and symbol with the given label from spectrum
 ///
 /// # Arguments
+/// * `label` - label of the concept and symbol
 /// * `spectrum` - representation to generate for
 /// * `radius` - initial radius of the concept
+/// * `structure` - structure of the covariance of the concept
 ///
-pub fn gen_concept_symbol(spectrum: Spectrum, radius: f64) -> (Concept, Symbol) {
-    let label = generate_label();
-    let concept = Concept::new(label, spectrum.point, radius);
-    let symbol = Symbol::new(label, spectrum.length);
+pub fn gen_concept_symbol(
+    label: Label,
+    spectrum: Spectrum,
+    radius: f64,
+    structure: Structure,
+) -> (Concept, Symbol) {
+    let concept = Concept::new(label, spectrum.point, radius, structure);
+    let mut symbol = Symbol::new(label, spectrum.length);
+    symbol.span = spectrum.span;
+    symbol.frames = spectrum.frames;
     (concept, symbol)
 }
 
 /// Identifier connecting semantic concepts to episodic symbols
 pub type Label = usize;
 
-// TODO: Ensure no label repetition in a given dimension
-/// Generates a random label
-fn generate_label() -> Label {
-    rand::thread_rng().gen()
-}
-
 /// First and second statistical moments specifying a multidimensional Gaussian
 /// Used for updating the categorical region after a new concept is added.
+///
+/// The prior is a normal-inverse-Wishart with a single pseudo-observation at
+/// the concept that founded the category, with an isotropic covariance derived
+/// from the initial radius. The posterior after n concepts with sample mean x̄
+/// and scatter S is then
+///   mean = (μ₀ + n x̄) / (n + 1)
+///   covariance = (Σ₀ + S + n / (n + 1) (x̄ - μ₀)(x̄ - μ₀)ᴴ) / n
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Moments {
+    /// Number of concepts the moments have been updated with
+    pub count: usize,
     /// Sample mean (first sample moment)
-    sample_mean: Vector,
-    /// Sample variance (second sample moment)
-    sample_variance: Vector,
+    pub sample_mean: Vector,
+    /// Sum of the squared deviations from the sample mean of each component
+    pub sample_scatter: Array1<f64>,
+    /// Principal directions of the deviations from the sample mean
+    pub sample_factors: Array2<c64>,
     /// Prior mean (first prior moment)
-    prior_mean: Vector,
-    /// Prior variance (second prior moment)
-    prior_variance: Vector,
+    pub prior_mean: Vector,
+    /// Prior variance of each component (second prior moment)
+    pub prior_variance: Array1<f64>,
+    /// Posterior mean (first posterior moment)
+    pub posterior_mean: Vector,
+    /// Posterior covariance (second posterior moment)
+    pub posterior_variance: Covariance,
+    /// Structure of the covariance
+    pub structure: Structure,
 }
 
 impl Moments {
-    pub fn empty(v: &Vector) -> Moments {
-        let shape = v.dim();
+    /// Returns the prior moments of a category founded by the given vector
+    ///
+    /// # Arguments
+    /// * `vector` - prior mean of the category
+    /// * `radius` - radius containing the prior mass of the category
+    /// * `structure` - structure of the covariance
+    ///
+    pub fn new(vector: Vector, radius: f64, structure: Structure) -> Moments {
+        let n = vector.len();
+        let variance = (radius.powi(2) / quantile(n)).max(MIN_VARIANCE);
+        let prior_variance = Array1::from_elem(n, variance);
         Moments {
-            sample_mean: Vector::zeros(shape),
-            sample_variance: Vector::zeros(shape),
-            prior_mean: Vector::zeros(shape),
-            prior_variance: Vector::zeros(shape),
+            count: 0,
+            sample_mean: vector.clone(),
+            sample_scatter: Array1::zeros(n),
+            sample_factors: Array2::zeros((n, 0)),
+            prior_mean: vector.clone(),
+            posterior_variance: Covariance::diagonal(prior_variance.clone()),
+            prior_variance,
+            posterior_mean: vector,
+            structure,
+        }
+    }
+
+    /// Incorporates the given point into the sample moments (Welford's method)
+    /// and recomputes the posterior.
+    ///
+    /// # Arguments
+    /// * `x` - point to update the moments with
+    ///
+    pub fn update(&mut self, x: &Vector) {
+        self.count += 1;
+        let n = self.count as f64;
+        let deviation = x - &self.sample_mean;
+        self.sample_mean = &self.sample_mean + &(&deviation / n);
+
+        // S ← S + (n - 1) / n d dᴴ
+        let weight = (n - 1.0) / n;
+        self.sample_scatter.zip_mut_with(&deviation, |s, d| *s += weight * d.norm_sqr());
+        let rank = self.structure.rank();
+        if rank > 0 && self.count > 1 {
+            let column = deviation.mapv(|d| d * weight.sqrt());
+            self.sample_factors = truncate(append(&self.sample_factors, &column), rank);
+        }
+        self.posterior();
+    }
+
+    /// Pools the sample moments of the given moments into these moments
+    /// (Chan's method) and recomputes the posterior. The prior is kept.
+    ///
+    /// # Arguments
+    /// * `other` - moments to absorb
+    ///
+    pub fn merge(&mut self, other: &Moments) {
+        let (n1, n2) = (self.count as f64, other.count as f64);
+        let n = n1 + n2;
+        if n2 == 0.0 {
+            return;
+        }
+        let delta = &other.sample_mean - &self.sample_mean;
+        let weight = n1 * n2 / n;
+        self.sample_mean = (&(&self.sample_mean * n1) + &(&other.sample_mean * n2)) / n;
+
+        // S ← S₁ + S₂ + n₁ n₂ / n δ δᴴ
+        self.sample_scatter = &self.sample_scatter + &other.sample_scatter;
+        self.sample_scatter.zip_mut_with(&delta, |s, d| *s += weight * d.norm_sqr());
+        let rank = self.structure.rank();
+        if rank > 0 {
+            let mut columns = self.sample_factors.clone();
+            for column in other.sample_factors.gencolumns() {
+                columns = append(&columns, &column.to_owned());
+            }
+            columns = append(&columns, &delta.mapv(|d| d * weight.sqrt()));
+            self.sample_factors = truncate(columns, rank);
+        }
+        self.count += other.count;
+        self.posterior();
+    }
+
+    /// Returns the moments of a part of the category, estimated from a sample
+    /// of its points and scaled to stand for the given number of concepts.
+    /// The prior variance is kept, and the prior mean is the sampled point
+    /// nearest the sample mean, as if that point had founded the part.
+    ///
+    /// # Arguments
+    /// * `points` - sample of the points of the part, at least one
+    /// * `count` - number of concepts the part stands for
+    ///
+    pub fn part(&self, points: &[Vector], count: usize) -> Moments {
+        let mut part = Moments {
+            count: 0,
+            sample_mean: points[0].clone(),
+            sample_scatter: Array1::zeros(points[0].len()),
+            sample_factors: Array2::zeros((points[0].len(), 0)),
+            ..self.clone()
+        };
+        for point in points {
+            part.update(point);
         }
+        let scale = count as f64 / points.len() as f64;
+        part.sample_scatter *= scale;
+        part.sample_factors.mapv_inplace(|f| f * scale.sqrt());
+        part.count = count;
+        let distance = |point: &Vector| (point - &part.sample_mean).iter().map(|d| d.norm_sqr()).sum::<f64>();
+        part.prior_mean = points.iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap()
+            .clone();
+        part.posterior();
+        part
+    }
+
+    /// Returns the radius containing the prior mass of the category
+    pub fn prior_radius(&self) -> f64 {
+        let n = self.prior_variance.len();
+        (self.prior_variance.mean().unwrap_or(0.0) * quantile(n)).sqrt()
+    }
+
+    /// Recomputes the posterior mean and covariance from the sample moments
+    fn posterior(&mut self) {
+        let n = self.count as f64;
+        self.posterior_mean = (&self.prior_mean + &(&self.sample_mean * n)) / (n + 1.0);
+
+        let bias = &self.sample_mean - &self.prior_mean;
+        let shrinkage = n / (n + 1.0);
+        let degrees = n.max(1.0);
+        let mut variances = &self.prior_variance + &self.sample_scatter;
+        variances.zip_mut_with(&bias, |v, b| *v = (*v + shrinkage * b.norm_sqr()) / degrees);
+
+        let rank = self.structure.rank();
+        self.posterior_variance = if rank == 0 {
+            Covariance::diagonal(variances)
+        } else {
+            let column = bias.mapv(|b| b * shrinkage.sqrt());
+            let factors = truncate(append(&self.sample_factors, &column), rank)
+                / degrees.sqrt();
+            let residual = (variances - row_energy(&factors)).mapv(|v| v.max(MIN_VARIANCE));
+            Covariance::new(residual, factors)
+        };
     }
 }
 
@@ -94,82 +244,102 @@ impl Hash for Concept {
 }
 
 impl Concept {
-    /// Returns an empty concept without spectrum or length
-//    pub fn empty() -> Concept {
-//        let spectrum = Spectrum {
-//            point: Vector::empty(),
-//            length: 0,
-//        };
-//        Concept::new(0, spectrum.point, 0.0)
-//    }
-
     /// Returns a new concept.
     ///
     /// # Arguments
     /// * `label` - identifier
-    /// * `spectrum` - representation of the concept
+    /// * `vector` - representation of the concept
     /// * `radius` - initial radius of the category
+    /// * `structure` - structure of the covariance of the category
     ///
-    pub fn new(label: Label, vector: Vector, radius: f64) -> Concept {
+    pub fn new(label: Label, vector: Vector, radius: f64, structure: Structure) -> Concept {
         Concept {
             label,
             location: Location {
                 centroid: vector.clone(),
                 radius,
             },
-            moments: Moments {
-                sample_mean: vector.clone(),
-                sample_variance: Vector::default(vector.len()),
-                prior_mean: vector.clone(),
-                prior_variance: {
-                    let fill = c64::new((radius / 3.0).powi(2), 0.0)
-                        / vector.len() as f64;
-                    Vector::from_elem(vector.len(), fill)
-                },
-            },
+            moments: Moments::new(vector, radius, structure),
         }
     }
 
-    // TODO: Use Array2 directly
     /// Posterior update of the Gaussian representing the category.
     ///
     /// # Arguments
     /// * `concept` - concept to be update the moments with
-    /// * `count` - number of times the category has been seen
-    ///
-    pub fn update(&mut self, concept: Concept, count: usize) {
-        let mp = self.moments.prior_mean.clone();
-        let vp = self.moments.prior_variance.clone();
-        let ms = self.moments.sample_mean.clone();
-        let vs = self.moments.sample_variance.clone();
-        let x = concept.location.centroid;
-        let mut u = Moments::empty(&x);
-
-        u.sample_mean = &ms + &((&x - &ms) / count as f64);
-        u.sample_variance = if count == 1 { vs } else {
-            &vs + &((-&vs + (&x - &u.sample_mean) * (&x - &ms)) / count as f64)
+    ///
+    pub fn update(&mut self, concept: Concept) {
+        self.moments.update(&concept.location.centroid);
+        self.location = Location {
+            centroid: self.moments.posterior_mean.clone(),
+            radius: self.moments.posterior_variance.radius(),
         };
+    }
 
-        let any_zero = (&vp + &u.sample_variance).iter()
-            .any(|c| AbsDiff::default().eq(&0f64, &c.norm()));
+    /// Absorbs the given category into this one, pooling their moments.
+    ///
+    /// # Arguments
+    /// * `other` - category to merge into this one
+    ///
+    pub fn merge(&mut self, other: &Concept) {
+        self.moments.merge(&other.moments);
+        self.location = Location {
+            centroid: self.moments.posterior_mean.clone(),
+            radius: self.moments.posterior_variance.radius(),
+        };
+    }
 
-        if any_zero {
-            u.prior_mean = mp;
-            u.prior_variance = vp;
-        } else {
-            u.prior_mean = (&u.sample_mean * &mp + &vp * &x)
-                / (&vp + &u.sample_variance);
-            u.prior_variance = if count == 1 { vp } else {
-                &u.sample_variance * &vp
-                    / (&u.sample_variance + &vp)
-            }
+    /// Returns the part of this category made of the given points, as
+    /// `Moments::part` estimates it, with the given label.
+    ///
+    /// # Arguments
+    /// * `label` - identifier of the part
+    /// * `points` - sample of the points of the part, at least one
+    /// * `count` - number of concepts the part stands for
+    ///
+    pub fn part(&self, label: Label, points: &[Vector], count: usize) -> Concept {
+        let moments = self.moments.part(points, count);
+        Concept {
+            label,
+            location: Location {
+                centroid: moments.posterior_mean.clone(),
+                radius: moments.posterior_variance.radius(),
+            },
+            moments,
         }
-        let location = Location {
-            centroid: u.prior_mean.clone(),
-            radius: (u.prior_mean.map(c64::sqrt) * c64::new(3f64, 0f64)).norm(),
-        };
-        self.location = location;
-        self.moments = u;
+    }
+
+    /// Bhattacharyya coefficient between the posterior Gaussians of the two
+    /// categories, from 0 (disjoint) to 1 (identical). Only the variance of
+    /// each component is considered, even if the covariance is low-rank.
+    ///
+    /// # Arguments
+    /// * `other` - category to measure the overlap with
+    ///
+    pub fn overlap(&self, other: &Concept) -> f64 {
+        let a = self.moments.posterior_variance.variances();
+        let b = other.moments.posterior_variance.variances();
+        let delta = &self.moments.posterior_mean - &other.moments.posterior_mean;
+        let distance: f64 = delta.iter().zip(a.iter().zip(b.iter()))
+            .map(|(d, (a, b))| {
+                d.norm_sqr() / (2.0 * (a + b)) + ((a + b) / (2.0 * (a * b).sqrt())).ln()
+            })
+            .sum();
+        (-distance).exp()
+    }
+
+    /// Natural logarithm of the density of the given vector under the
+    /// circular complex Gaussian with the concept's posterior moments.
+    ///
+    /// # Arguments
+    /// * `vector` - point at which to evaluate the density
+    ///
+    pub fn log_likelihood(&self, vector: &Vector) -> f64 {
+        let covariance = &self.moments.posterior_variance;
+        let deviation = vector - &self.moments.posterior_mean;
+        -(covariance.len() as f64) * PI.ln()
+            - covariance.log_determinant()
+            - covariance.mahalanobis(&deviation)
     }
 }
 
@@ -182,6 +352,10 @@ pub struct Symbol {
     pub content: String,
     /// Number of symbols subtended by this symbol
     pub length: usize,
+    /// Positions of the subordinate symbols subtended by this symbol
+    pub span: Span,
+    /// Positions of the frames of the source subtended by this symbol
+    pub frames: Span,
 }
 
 impl Symbol {
@@ -196,6 +370,8 @@ impl Symbol {
             label,
             content: label.to_string(),
             length,
+            span: Span::default(),
+            frames: Span::default(),
         }
     }
 }
@@ -203,7 +379,144 @@ impl Symbol {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::covariance::adjoint;
+
+    fn observations() -> Vec<Vector> {
+        (0..6).map(|j| (0..4)
+            .map(|i| {
+                let t = (j * 4 + i) as f64;
+                c64::new((1.3 * t).sin() + i as f64, (0.7 * t).cos())
+            })
+            .collect())
+            .collect()
+    }
+
+    /// Batch posterior computed with dense matrices, as in the Moments doc
+    fn reference(xs: &[Vector], radius: f64) -> (Vector, Array2<c64>) {
+        let n = xs.len() as f64;
+        let dim = xs[0].len();
+        let prior_mean = xs[0].clone();
+        let mut mean = Vector::zeros(dim);
+        for x in xs {
+            mean += x;
+        }
+        let mean = mean / n;
+        let outer = |v: &Vector| {
+            let column = v.clone().into_shape((dim, 1)).unwrap();
+            crate::covariance::product(&column, &adjoint(&column))
+        };
+        let mut covariance = Array2::<c64>::eye(dim) * c64::new(radius.powi(2) / quantile(dim), 0.0);
+        for x in xs {
+            covariance = covariance + outer(&(x - &mean));
+        }
+        covariance = covariance + outer(&(&mean - &prior_mean)) * c64::new(n / (n + 1.0), 0.0);
+        let posterior_mean = (&prior_mean + &(&mean * n)) / (n + 1.0);
+        (posterior_mean, covariance / c64::new(n, 0.0))
+    }
+
+    fn concept(xs: &[Vector], structure: Structure) -> Concept {
+        let mut concept = Concept::new(1, xs[0].clone(), 2.0, structure);
+        for x in xs {
+            concept.update(Concept::new(2, x.clone(), 2.0, structure));
+        }
+        concept
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-8 * (1.0 + b.abs())
+    }
+
+    #[test]
+    fn test_new() {
+        let x = observations().remove(0);
+        let concept = Concept::new(1, x.clone(), 2.0, Structure::Diagonal);
+        assert!(close(concept.location.radius, 2.0));
+        assert!(close(concept.moments.posterior_variance.radius(), 2.0));
+        let deviation = &concept.location.centroid - &x;
+        assert!(deviation.iter().all(|d| d.norm() < 1e-12));
+    }
+
+    #[test]
+    fn test_update_single() {
+        // The founding concept alone leaves the prior unchanged
+        let xs = observations();
+        let concept = concept(&xs[..1], Structure::Diagonal);
+        assert!(close(concept.location.radius, 2.0));
+        assert_eq!(concept.moments.count, 1);
+    }
+
+    #[test]
+    fn test_update_diagonal() {
+        let xs = observations();
+        let concept = concept(&xs, Structure::Diagonal);
+        let (mean, covariance) = reference(&xs, 2.0);
+        for (a, b) in concept.location.centroid.iter().zip(mean.iter()) {
+            assert!((a - b).norm() < 1e-8);
+        }
+        let variances = concept.moments.posterior_variance.variances();
+        for (i, v) in variances.iter().enumerate() {
+            assert!(close(*v, covariance[[i, i]].re));
+        }
+        let trace: f64 = variances.sum();
+        assert!(close(concept.location.radius, (trace / 4.0 * quantile(4)).sqrt()));
+    }
+
+    #[test]
+    fn test_merge() {
+        let xs = observations();
+        for &structure in &[Structure::Diagonal, Structure::LowRank(4)] {
+            let whole = concept(&xs, structure);
+            let mut first = concept(&xs[..2], structure);
+            let mut second = concept(&xs[..2], structure);
+            second.moments = Moments::new(xs[0].clone(), 2.0, structure);
+            for x in &xs[2..] {
+                second.moments.update(x);
+            }
+            first.merge(&second);
+            assert_eq!(first.moments.count, whole.moments.count);
+            let expected = whole.moments.posterior_variance.dense();
+            let actual = first.moments.posterior_variance.dense();
+            for (a, b) in actual.iter().zip(expected.iter()) {
+                assert!((a - b).norm() < 1e-8);
+            }
+        }
+    }
 
     #[test]
-    fn test() {}
+    fn test_overlap() {
+        let xs = observations();
+        let a = Concept::new(1, xs[0].clone(), 2.0, Structure::Diagonal);
+        assert!(close(a.overlap(&a), 1.0));
+        let b = Concept::new(2, &xs[0] + &Vector::from_elem(4, c64::new(1.0, 0.0)), 2.0, Structure::Diagonal);
+        let c = Concept::new(3, &xs[0] + &Vector::from_elem(4, c64::new(5.0, 0.0)), 2.0, Structure::Diagonal);
+        assert!(a.overlap(&b) < 1.0);
+        assert!(a.overlap(&c) < a.overlap(&b));
+        assert!(close(a.overlap(&b), b.overlap(&a)));
+    }
+
+    #[test]
+    fn test_update_low_rank() {
+        let xs = observations();
+        let (_, covariance) = reference(&xs, 2.0);
+
+        // Enough directions to represent the covariance exactly
+        let full = concept(&xs, Structure::LowRank(4));
+        let dense = full.moments.posterior_variance.dense();
+        for (a, b) in dense.iter().zip(covariance.iter()) {
+            assert!((a - b).norm() < 1e-8);
+        }
+        let y = &xs[1] * c64::new(1.5, -0.5);
+        let diagonal = concept(&xs, Structure::Diagonal);
+        let expected = full.log_likelihood(&y);
+        assert!(expected.is_finite());
+        assert!((expected - diagonal.log_likelihood(&y)).abs() > 1e-6);
+
+        // Fewer directions keep the variance of each component exact
+        let truncated = concept(&xs, Structure::LowRank(1));
+        assert_eq!(truncated.moments.posterior_variance.factors.ncols(), 1);
+        let variances = truncated.moments.posterior_variance.variances();
+        for (i, v) in variances.iter().enumerate() {
+            assert!(close(*v, covariance[[i, i]].re));
+        }
+    }
 }
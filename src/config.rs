@@ -1,3 +1,15 @@
+use crate::abstraction::Interpolation;
+use crate::annotation::Format;
+use crate::categorization::Categorizer;
+use crate::covariance::Structure;
+use crate::error::PredictError;
+use crate::markov_model::Smoothing;
+use crate::reduction::Reduction;
+use crate::segmentation::Segmentation;
+use crate::transformation::Transformation;
+use crate::metric::Distance;
+use log::LevelFilter;
+
 /// Configuration for loading/saving, as well as parameter dimensions
 pub struct Config {
     /// Path of WAV file to load audio from
@@ -6,35 +18,191 @@ pub struct Config {
     pub save_at: String,
     /// Path of file to load dimensions from
     pub init_with: String,
+    /// Formats of the annotation files written next to `save_at`
+    pub annotations: Vec<Format>,
+    /// Number of samples of the source in each frame perceived by level 0
+    pub frame_size: usize,
+    /// Number of samples of the source between the starts of consecutive frames
+    pub hop_size: usize,
     /// Scale of the initial radius of a category
     pub radius_scale: f64,
     /// Number of real + virtual concepts in a trajectory
     pub resolution: u16,
+    /// Method of sampling a trajectory to resolution concepts
+    pub interpolation: Interpolation,
+    /// Transform of the trajectories abstracted at each level (Fourier if absent)
+    pub transformations: Vec<Transformation>,
+    /// Reduction of the coefficients abstracted at each level (full if absent)
+    pub reductions: Vec<Reduction>,
     /// Maximum number of dimensions in the memory
     pub max_depth: u16,
+    /// Whether each dimension perceives on its own thread, passing the spectra
+    /// it abstracts to the dimension above through a bounded channel
+    pub pipelined: bool,
+    /// Number of spectra a channel of the pipeline holds before its sender waits
+    pub channel_capacity: usize,
+    /// Whether to show a progress bar while perceiving the source
+    pub progress: bool,
+    /// Most detailed level of the diagnostics that are logged
+    pub verbosity: LevelFilter,
+    /// Metric deciding category membership at each level (Euclidean if absent)
+    pub metrics: Vec<Distance>,
+    /// Method of assigning a perceived concept to a category
+    pub categorizer: Categorizer,
+    /// Structure of the covariance of concepts at each level (diagonal if absent)
+    pub structures: Vec<Structure>,
+    /// Number of concepts perceived by a dimension between consolidations,
+    /// in which overlapping concepts merge and bimodal ones split (0 for never)
+    pub consolidation_period: usize,
+    /// Minimum Bhattacharyya coefficient of two concepts for them to merge
+    pub merge_threshold: f64,
+    /// Minimum Ashman's D of the exemplars of a concept for it to split
+    pub split_threshold: f64,
+    /// Number of recent exemplars kept per concept to detect bimodality
+    pub exemplars: usize,
+    /// Number of concepts perceived by a dimension between decaying the counts
+    /// of its markov models and forgetting its rare concepts (0 for never)
+    pub forgetting_period: usize,
+    /// Factor between 0 and 1 by which counts are scaled when forgetting
+    pub decay: f64,
+    /// Concepts with a smaller count after decay are forgotten
+    pub prune_below: usize,
+    /// Maximum number of symbols in the episodic memory (0 for unbounded)
+    pub history: usize,
+    /// Length of the sequences counted by the markov model of each dimension,
+    /// i.e. one more than the length of the context (2 for a bigram model)
+    pub order: usize,
+    /// Method of estimating the probability of the next label from the counts
+    pub smoothing: Smoothing,
+    /// Segmenter deciding the boundaries at each level (unigram drop if absent)
+    pub segmenters: Vec<Segmentation>,
 }
 
 impl Config {
     /// Returns a Config without paths, and default values for dimension params.
-    pub fn default() -> Result<Config, &'static str> {
+    pub fn default() -> Result<Config, PredictError> {
         Ok(Config {
             load_from: "export.wav".to_string(),
             save_at: "dimensions".to_string(),
             init_with: "dimensions".to_string(),
+            annotations: Vec::new(),
+            frame_size: 16,
+            hop_size: 16,
             radius_scale: 1.0,
             resolution: 16,
+            interpolation: Interpolation::Step,
+            transformations: vec![Transformation::Fourier; 4],
+            reductions: vec![Reduction::Full; 4],
             max_depth: 4,
+            pipelined: false,
+            channel_capacity: 64,
+            progress: true,
+            verbosity: LevelFilter::Info,
+            metrics: vec![Distance::Euclidean; 4],
+            categorizer: Categorizer::Hard,
+            structures: vec![Structure::Diagonal; 4],
+            consolidation_period: 128,
+            merge_threshold: 0.5,
+            split_threshold: 4.0,
+            exemplars: 16,
+            forgetting_period: 0,
+            decay: 0.5,
+            prune_below: 1,
+            history: 0,
+            order: 2,
+            smoothing: Smoothing::WittenBell,
+            segmenters: vec![Segmentation::UnigramDrop; 4],
         })
     }
 
+    /// Returns the metric used for category membership at the given level
+    ///
+    /// # Arguments
+    /// * `level` - index of depth in memory hierarchy
+    ///
+    pub fn metric(&self, level: u16) -> Distance {
+        self.metrics.get(level as usize).copied().unwrap_or_default()
+    }
+
+    /// Returns the structure of the covariance of concepts at the given level
+    ///
+    /// # Arguments
+    /// * `level` - index of depth in memory hierarchy
+    ///
+    pub fn structure(&self, level: u16) -> Structure {
+        self.structures.get(level as usize).copied().unwrap_or_default()
+    }
+
+    /// Returns the segmenter deciding the boundaries at the given level
+    ///
+    /// # Arguments
+    /// * `level` - index of depth in memory hierarchy
+    ///
+    pub fn segmenter(&self, level: u16) -> Segmentation {
+        self.segmenters.get(level as usize).cloned().unwrap_or_default()
+    }
+
+    /// Returns the transform of the trajectories abstracted at the given level
+    ///
+    /// # Arguments
+    /// * `level` - index of depth in memory hierarchy
+    ///
+    pub fn transformation(&self, level: u16) -> Transformation {
+        self.transformations.get(level as usize).cloned().unwrap_or_default()
+    }
+
+    /// Returns the reduction of the coefficients abstracted at the given level
+    ///
+    /// # Arguments
+    /// * `level` - index of depth in memory hierarchy
+    ///
+    pub fn reduction(&self, level: u16) -> Reduction {
+        self.reductions.get(level as usize).copied().unwrap_or_default()
+    }
+
+    /// Returns an error describing the first parameter that cannot be used,
+    /// if any, so that a bad configuration fails before perceiving anything
+    pub fn validate(&self) -> Result<(), PredictError> {
+        let invalid = |message: &str| Err(PredictError::Configuration(message.to_string()));
+        if self.frame_size == 0 || self.hop_size == 0 {
+            return invalid("frames must have a positive size and hop");
+        }
+        if self.resolution == 0 {
+            return invalid("trajectories must be sampled at a positive resolution");
+        }
+        if self.order == 0 {
+            return invalid("markov models must count sequences of at least one label");
+        }
+        if self.radius_scale.is_nan() || self.radius_scale <= 0.0 {
+            return invalid("the radius scale must be positive");
+        }
+        if !(0.0..=1.0).contains(&self.decay) {
+            return invalid("the decay must be between 0 and 1");
+        }
+        let empty = self.reductions.iter().any(|reduction| match reduction {
+            Reduction::Full => false,
+            Reduction::Truncate(rows) => *rows == 0,
+            Reduction::RandomProjection { dimensions, .. } => *dimensions == 0,
+        });
+        if empty {
+            return invalid("reductions must keep at least one coefficient");
+        }
+        let unfit = self.transformations.iter().any(|transformation| matches!(transformation,
+            Transformation::LearnedPca { components, trajectories } if *components == 0 || *trajectories == 0));
+        if unfit {
+            return invalid("principal axes must be fitted to at least one trajectory and keep one axis");
+        }
+        Ok(())
+    }
+
     /// Returns a Config parameterized by the given command-line arguments.
     ///
     /// # Arguments
     /// * `args` - list of string arguments from the command-line
     ///
-    pub fn new(args: &[String]) -> Result<Config, &'static str> {
-        unimplemented!();
+    pub fn new(args: &[String]) -> Result<Config, PredictError> {
         // TODO: Parse arguments and create config
+        Err(PredictError::Configuration(format!("cannot parse {} arguments yet", args.len())))
     }
 }
 
@@ -43,5 +211,24 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test() {}
+    fn test_metric() {
+        let mut config = Config::default().unwrap();
+        config.metrics = vec![Distance::Cosine];
+        assert_eq!(config.metric(0), Distance::Cosine);
+        assert_eq!(config.metric(3), Distance::Euclidean);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = Config::default().unwrap();
+        assert!(config.validate().is_ok());
+        config.hop_size = 0;
+        assert!(matches!(config.validate(), Err(PredictError::Configuration(_))));
+        config.hop_size = 16;
+        config.reductions = vec![Reduction::Truncate(0)];
+        assert!(matches!(config.validate(), Err(PredictError::Configuration(_))));
+        config.reductions = Vec::new();
+        config.transformations = vec![Transformation::LearnedPca { components: 1, trajectories: 0 }];
+        assert!(matches!(config.validate(), Err(PredictError::Configuration(_))));
+    }
 }
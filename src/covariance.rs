@@ -0,0 +1,433 @@
+use crate::spectrum::Vector;
+use ndarray::{Array1, Array2, Axis, s};
+use ndarray_linalg::types::c64;
+use serde::{Serialize, Deserialize};
+
+/// Number of standard normal deviations at which the radius of a concept is
+/// taken, i.e. the radius contains about 99.87% of the concept's mass
+const CONFIDENCE: f64 = 3.0;
+
+/// Maximum number of Jacobi sweeps before giving up on convergence
+const SWEEPS: usize = 64;
+
+/// Structure of the covariance matrix of each concept in a dimension
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Structure {
+    /// Independent variance for each component
+    #[default]
+    Diagonal,
+    /// Diagonal plus the given number of principal directions
+    LowRank(usize),
+}
+
+impl Structure {
+    /// Returns the number of principal directions beyond the diagonal
+    pub fn rank(&self) -> usize {
+        match self {
+            Structure::Diagonal => 0,
+            Structure::LowRank(rank) => *rank,
+        }
+    }
+}
+
+/// Covariance matrix of a circular complex Gaussian of the form D + F Fᴴ,
+/// where D is diagonal and the columns of F are its principal directions.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Covariance {
+    /// Variance of each component not explained by the factors (D)
+    pub diagonal: Array1<f64>,
+    /// Principal directions scaled by their variance (F), possibly no columns
+    pub factors: Array2<c64>,
+    /// Low-rank correction of the inverse, such that Σ⁻¹ = D⁻¹ - W Wᴴ
+    whitening: Array2<c64>,
+    /// Natural logarithm of the determinant
+    log_determinant: f64,
+}
+
+impl Covariance {
+    /// Returns a covariance with the given diagonal and principal directions
+    ///
+    /// # Arguments
+    /// * `diagonal` - strictly positive variance of each component
+    /// * `factors` - matrix of which the columns are the principal directions
+    ///
+    pub fn new(diagonal: Array1<f64>, factors: Array2<c64>) -> Covariance {
+        let log_diagonal: f64 = diagonal.iter().map(|d| d.ln()).sum();
+        if factors.ncols() == 0 {
+            return Covariance {
+                whitening: Array2::zeros((diagonal.len(), 0)),
+                log_determinant: log_diagonal,
+                diagonal,
+                factors,
+            };
+        }
+
+        // Woodbury identity with M = I + Fᴴ D⁻¹ F = V Λ Vᴴ
+        let mut scaled = factors.clone();
+        for (mut row, d) in scaled.genrows_mut().into_iter().zip(diagonal.iter()) {
+            row.mapv_inplace(|f| f / *d);
+        }
+        let mut inner = product(&adjoint(&factors), &scaled);
+        for i in 0..inner.nrows() {
+            inner[[i, i]] += c64::new(1.0, 0.0);
+        }
+        let (values, vectors) = eigh(&inner);
+        let mut whitening = product(&scaled, &vectors);
+        for (mut column, value) in whitening.gencolumns_mut().into_iter().zip(values.iter()) {
+            column.mapv_inplace(|w| w / value.sqrt());
+        }
+        Covariance {
+            log_determinant: log_diagonal + values.iter().map(|v| v.ln()).sum::<f64>(),
+            whitening,
+            diagonal,
+            factors,
+        }
+    }
+
+    /// Returns a covariance with only independent variances
+    ///
+    /// # Arguments
+    /// * `diagonal` - strictly positive variance of each component
+    ///
+    pub fn diagonal(diagonal: Array1<f64>) -> Covariance {
+        let n = diagonal.len();
+        Covariance::new(diagonal, Array2::zeros((n, 0)))
+    }
+
+    /// Number of components of the Gaussian
+    pub fn len(&self) -> usize {
+        self.diagonal.len()
+    }
+
+    /// Decides whether the Gaussian has no components
+    pub fn is_empty(&self) -> bool {
+        self.diagonal.is_empty()
+    }
+
+    /// Returns the variance of each component, i.e. the diagonal of D + F Fᴴ
+    pub fn variances(&self) -> Array1<f64> {
+        let mut variances = self.diagonal.clone();
+        for (v, row) in variances.iter_mut().zip(self.factors.genrows()) {
+            *v += row.iter().map(|f| f.norm_sqr()).sum::<f64>();
+        }
+        variances
+    }
+
+    /// Returns the sum of the variances of the components
+    pub fn trace(&self) -> f64 {
+        self.variances().sum()
+    }
+
+    /// Returns the natural logarithm of the determinant
+    pub fn log_determinant(&self) -> f64 {
+        self.log_determinant
+    }
+
+    /// Returns the squared Mahalanobis distance of the deviation, dᴴ Σ⁻¹ d
+    ///
+    /// # Arguments
+    /// * `deviation` - difference between a point and the mean
+    ///
+    pub fn mahalanobis(&self, deviation: &Vector) -> f64 {
+        let diagonal: f64 = deviation.iter().zip(self.diagonal.iter())
+            .map(|(d, v)| d.norm_sqr() / v)
+            .sum();
+        let correction: f64 = self.whitening.gencolumns().into_iter()
+            .map(|w| w.iter().zip(deviation.iter())
+                .map(|(w, d)| w.conj() * d)
+                .sum::<c64>()
+                .norm_sqr())
+            .sum();
+        (diagonal - correction).max(0.0)
+    }
+
+    /// Returns the full matrix D + F Fᴴ
+    pub fn dense(&self) -> Array2<c64> {
+        let mut matrix = product(&self.factors, &adjoint(&self.factors));
+        for (i, d) in self.diagonal.iter().enumerate() {
+            matrix[[i, i]] += c64::new(*d, 0.0);
+        }
+        matrix
+    }
+
+    /// Returns the radius of the sphere around the mean containing the
+    /// concept's mass if it were spread evenly over all components
+    pub fn radius(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let n = self.len() as f64;
+        (self.trace() / n * quantile(self.len())).sqrt()
+    }
+}
+
+/// Returns the squared Mahalanobis distance within which a circular complex
+/// Gaussian with n components has its mass, at the `CONFIDENCE` level.
+/// The squared distance is Gamma(n, 1), i.e. half a chi-square with 2n degrees
+/// of freedom, of which the quantile is the Wilson-Hilferty approximation.
+///
+/// # Arguments
+/// * `n` - number of complex components
+///
+pub fn quantile(n: usize) -> f64 {
+    let k = 2.0 * n as f64;
+    let h = 2.0 / (9.0 * k);
+    k * (1.0 - h + CONFIDENCE * h.sqrt()).powi(3) / 2.0
+}
+
+/// Returns the best approximation of A Aᴴ by F Fᴴ with at most `rank` columns,
+/// where the columns of F are the principal directions of the columns of A.
+///
+/// # Arguments
+/// * `columns` - matrix A of which to approximate the outer product
+/// * `rank` - maximum number of columns of the approximation
+///
+pub fn truncate(columns: Array2<c64>, rank: usize) -> Array2<c64> {
+    if columns.ncols() <= rank {
+        return columns;
+    }
+    let gram = product(&adjoint(&columns), &columns);
+    let (_, vectors) = eigh(&gram);
+    product(&columns, &vectors.slice(s![.., ..rank]).to_owned())
+}
+
+/// Returns the columns of the matrix followed by the given column
+///
+/// # Arguments
+/// * `matrix` - matrix to extend
+/// * `column` - column to append
+///
+pub fn append(matrix: &Array2<c64>, column: &Vector) -> Array2<c64> {
+    let mut extended = Array2::zeros((column.len(), matrix.ncols() + 1));
+    extended.slice_mut(s![.., ..matrix.ncols()]).assign(matrix);
+    extended.column_mut(matrix.ncols()).assign(column);
+    extended
+}
+
+/// Returns the conjugate transpose of the matrix
+pub fn adjoint(matrix: &Array2<c64>) -> Array2<c64> {
+    matrix.t().mapv(|c| c.conj())
+}
+
+/// Returns the matrix product A B.
+/// Written out since BLAS is not linked for complex matrices.
+pub fn product(a: &Array2<c64>, b: &Array2<c64>) -> Array2<c64> {
+    let mut c = Array2::zeros((a.nrows(), b.ncols()));
+    for (i, row) in a.genrows().into_iter().enumerate() {
+        for (j, column) in b.gencolumns().into_iter().enumerate() {
+            c[[i, j]] = row.iter().zip(column.iter()).map(|(x, y)| x * y).sum();
+        }
+    }
+    c
+}
+
+/// Eigendecomposition of a Hermitian matrix H = V Λ Vᴴ.
+/// Returns the eigenvalues in descending order with the eigenvectors as the
+/// columns of V. The complex matrix A + iB is embedded as the real symmetric
+/// matrix [[A, -B], [B, A]], of which each eigenvalue appears twice.
+///
+/// # Arguments
+/// * `matrix` - Hermitian matrix to decompose
+///
+pub fn eigh(matrix: &Array2<c64>) -> (Array1<f64>, Array2<c64>) {
+    let m = matrix.nrows();
+    let mut real = Array2::zeros((2 * m, 2 * m));
+    for ((i, j), c) in matrix.indexed_iter() {
+        real[[i, j]] = c.re;
+        real[[i + m, j + m]] = c.re;
+        real[[i, j + m]] = -c.im;
+        real[[i + m, j]] = c.im;
+    }
+    let (values, vectors) = jacobi(real);
+    let mut order: Vec<usize> = (0..2 * m).collect();
+    order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+
+    // Each complex eigenvector u + iv appears as [u; v] and [-v; u]
+    let mut eigenvalues = Array1::zeros(m);
+    let mut eigenvectors = Array2::<c64>::zeros((m, m));
+    let mut found = 0;
+    for k in order {
+        if found == m {
+            break;
+        }
+        let column = vectors.column(k);
+        let mut w: Vector = (0..m).map(|i| c64::new(column[i], column[i + m])).collect();
+        for j in 0..found {
+            let basis = eigenvectors.column(j);
+            let projection: c64 = basis.iter().zip(w.iter()).map(|(b, w)| b.conj() * w).sum();
+            w.zip_mut_with(&basis, |w, b| *w -= projection * b);
+        }
+        let norm = w.iter().map(|c| c.norm_sqr()).sum::<f64>().sqrt();
+        if norm > 0.5 {
+            eigenvectors.column_mut(found).assign(&w.mapv(|c| c / norm));
+            eigenvalues[found] = values[k];
+            found += 1;
+        }
+    }
+    (eigenvalues, eigenvectors)
+}
+
+/// Cyclic Jacobi eigendecomposition of a real symmetric matrix.
+/// Returns the (unsorted) eigenvalues and the eigenvectors as columns.
+///
+/// # Arguments
+/// * `a` - real symmetric matrix to decompose
+///
+fn jacobi(mut a: Array2<f64>) -> (Array1<f64>, Array2<f64>) {
+    let m = a.nrows();
+    let mut v = Array2::eye(m);
+    let scale: f64 = a.iter().map(|x| x * x).sum::<f64>().max(f64::MIN_POSITIVE);
+    for _ in 0..SWEEPS {
+        let off: f64 = a.indexed_iter()
+            .filter(|((i, j), _)| i != j)
+            .map(|(_, x)| x * x)
+            .sum();
+        if off <= 1e-30 * scale {
+            break;
+        }
+        for p in 0..m {
+            for q in p + 1..m {
+                let apq = a[[p, q]];
+                if apq == 0.0 {
+                    continue;
+                }
+                let theta = (a[[q, q]] - a[[p, p]]) / (2.0 * apq);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..m {
+                    let (akp, akq) = (a[[k, p]], a[[k, q]]);
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+                for k in 0..m {
+                    let (apk, aqk) = (a[[p, k]], a[[q, k]]);
+                    a[[p, k]] = c * apk - s * aqk;
+                    a[[q, k]] = s * apk + c * aqk;
+                }
+                for k in 0..m {
+                    let (vkp, vkq) = (v[[k, p]], v[[k, q]]);
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    (a.diag().to_owned(), v)
+}
+
+/// Returns the diagonal of A Aᴴ, i.e. the squared norm of each row
+pub fn row_energy(columns: &Array2<c64>) -> Array1<f64> {
+    columns.map(|c| c.norm_sqr()).sum_axis(Axis(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hermitian() -> Array2<c64> {
+        let mut a = Array2::zeros((3, 3));
+        let entries = [
+            ((0, 0), c64::new(4.0, 0.0)),
+            ((1, 1), c64::new(3.0, 0.0)),
+            ((2, 2), c64::new(1.0, 0.0)),
+            ((0, 1), c64::new(1.0, 1.0)),
+            ((0, 2), c64::new(0.0, -0.5)),
+            ((1, 2), c64::new(0.25, 0.0)),
+        ];
+        for &((i, j), c) in entries.iter() {
+            a[[i, j]] = c;
+            a[[j, i]] = c.conj();
+        }
+        a
+    }
+
+    fn gram(columns: &Array2<c64>) -> Array2<c64> {
+        product(columns, &adjoint(columns))
+    }
+
+    fn close(a: &Array2<c64>, b: &Array2<c64>) -> bool {
+        a.iter().zip(b.iter()).all(|(x, y)| (x - y).norm() < 1e-8)
+    }
+
+    /// Reference inverse by Gauss-Jordan elimination
+    fn inverse(matrix: &Array2<c64>) -> Array2<c64> {
+        let n = matrix.nrows();
+        let mut a = matrix.clone();
+        let mut inv = Array2::<c64>::eye(n);
+        for i in 0..n {
+            let pivot = a[[i, i]];
+            for j in 0..n {
+                a[[i, j]] /= pivot;
+                inv[[i, j]] /= pivot;
+            }
+            for k in 0..n {
+                if k != i {
+                    let factor = a[[k, i]];
+                    for j in 0..n {
+                        a[[k, j]] = a[[k, j]] - factor * a[[i, j]];
+                        inv[[k, j]] = inv[[k, j]] - factor * inv[[i, j]];
+                    }
+                }
+            }
+        }
+        inv
+    }
+
+    #[test]
+    fn test_eigh() {
+        let a = hermitian();
+        let (values, vectors) = eigh(&a);
+        assert!(values[0] >= values[1] && values[1] >= values[2]);
+        let mut lambda = Array2::zeros((3, 3));
+        for i in 0..3 {
+            lambda[[i, i]] = c64::new(values[i], 0.0);
+        }
+        let reconstructed = product(&product(&vectors, &lambda), &adjoint(&vectors));
+        assert!(close(&a, &reconstructed));
+        assert!(close(&product(&adjoint(&vectors), &vectors), &Array2::eye(3)));
+    }
+
+    #[test]
+    fn test_truncate() {
+        let columns = hermitian();
+        assert!(close(&gram(&truncate(columns.clone(), 3)), &gram(&columns)));
+        let truncated = truncate(columns.clone(), 1);
+        assert_eq!(truncated.ncols(), 1);
+        let (values, _) = eigh(&gram(&columns));
+        let energy: f64 = row_energy(&truncated).sum();
+        assert!((energy - values[0]).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_mahalanobis() {
+        let diagonal = Array1::from(vec![1.0, 2.0, 0.5]);
+        let factors = hermitian().slice(s![.., ..2]).to_owned();
+        let covariance = Covariance::new(diagonal, factors);
+        let matrix = covariance.dense();
+        let deviation = Vector::from(vec![
+            c64::new(1.0, -1.0), c64::new(0.5, 2.0), c64::new(-3.0, 0.0)
+        ]);
+        let inv = inverse(&matrix);
+        let expected: c64 = (0..3).flat_map(|i| (0..3).map(move |j| (i, j)))
+            .map(|(i, j)| deviation[i].conj() * inv[[i, j]] * deviation[j])
+            .sum();
+        assert!((covariance.mahalanobis(&deviation) - expected.re).abs() < 1e-8);
+
+        let (values, _) = eigh(&matrix);
+        let log_determinant: f64 = values.iter().map(|v| v.ln()).sum();
+        assert!((covariance.log_determinant() - log_determinant).abs() < 1e-8);
+        assert!((covariance.trace() - values.sum()).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_radius() {
+        // Isotropic variance chosen such that the radius is 2
+        let n = 8;
+        let variance = 4.0 / quantile(n);
+        let covariance = Covariance::diagonal(Array1::from_elem(n, variance));
+        assert!((covariance.radius() - 2.0).abs() < 1e-9);
+        // Wilson-Hilferty is accurate to a few percent for the 3-sigma quantile
+        assert!(quantile(1) > 5.5 && quantile(1) < 7.0);
+    }
+}
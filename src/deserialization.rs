@@ -1,7 +1,64 @@
+use crate::dimension::Dimension;
+use crate::error::PredictError;
+use std::fs::File;
+use std::io::BufReader;
+
+/// Returns the dimensions of the memory saved at the given path, failing if
+/// the file cannot be decoded or the memory it holds is inconsistent
+///
+/// # Arguments
+/// * `path` - path of the file written by `serialization::save`
+///
+pub fn load(path: &str) -> Result<Vec<Dimension>, PredictError> {
+    let reader = BufReader::new(File::open(path)?);
+    let dimensions: Vec<Dimension> = serde_json::from_reader(reader).map_err(|error| {
+        if error.is_io() { PredictError::from(error) } else { PredictError::Corrupted(error.to_string()) }
+    })?;
+    for (level, dimension) in dimensions.iter().enumerate() {
+        if dimension.level() as usize != level {
+            return Err(PredictError::Corrupted(format!("level {} is saved as level {}", level, dimension.level())));
+        }
+        dimension.verify()?;
+    }
+    Ok(dimensions)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Config;
+    use crate::perception::process;
+    use crate::serialization::save;
+    use ndarray::Array1;
+    use ndarray_linalg::types::c64;
+    use std::fs;
 
     #[test]
-    fn test() {}
+    fn test() -> Result<(), PredictError> {
+        let mut config = Config::default()?;
+        config.resolution = 4;
+        config.max_depth = 2;
+        let signal = (0..12)
+            .map(|i| Array1::from(vec![c64::new(((i * 5) % 7) as f64, 0.0); 4]))
+            .collect();
+        let dimensions = process(&config, signal)?;
+        let path = std::env::temp_dir().join(format!("predict_memory_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        save(&dimensions, path)?;
+        let restored = load(path)?;
+        assert_eq!(restored.len(), dimensions.len());
+        for (a, b) in dimensions.iter().zip(restored.iter()) {
+            assert_eq!(a.level(), b.level());
+            assert_eq!(a.symbols().count(), b.symbols().count());
+            assert_eq!(a.segments(), b.segments());
+        }
+
+        // Levels out of order and truncated files are corrupted memory
+        save(&dimensions.into_iter().rev().collect::<Vec<Dimension>>(), path)?;
+        assert!(matches!(load(path), Err(PredictError::Corrupted(_))));
+        fs::write(path, "[{\"level\": 0")?;
+        assert!(matches!(load(path), Err(PredictError::Corrupted(_))));
+        fs::remove_file(path)?;
+        Ok(())
+    }
 }
@@ -1,14 +1,27 @@
-use crate::spectrum::{Spectrum, Signal, Vector};
+use crate::spectrum::{Span, Spectrum, Signal, Vector};
 use crate::concept_symbol::{gen_concept_symbol, Concept, Label, Symbol};
-use crate::markov_model::{BigramModel, UnigramModel};
-use crate::categorization::categorize;
-use crate::segmentation::segment;
-use crate::abstraction::{transform, interpolate};
-use std::collections::HashMap;
+use crate::markov_model::{NGramModel, Smoothing, UnigramModel};
+use crate::categorization::{categorize, categorize_soft, nearest, Categorizer, Posterior};
+use crate::segmentation::{Context, Information, Segmentation, Segmenter};
+use crate::reduction::Reducer;
+use crate::transformation::{Pca, Transform, Transformation};
+use crate::abstraction::{transform, interpolate, invert, Interpolation};
+use crate::config::Config;
+use crate::covariance::Structure;
+use crate::error::PredictError;
+use crate::metric::Distance;
+use crate::observer::{Event, Observer};
+use std::collections::{BTreeMap, HashMap};
+use log::{debug, trace};
 use serde::{Serialize, Deserialize};
+use ndarray_linalg::norm::Norm;
+use ndarray_linalg::types::c64;
+
+/// Number of 2-means iterations when looking for a split of a concept
+const BISECTIONS: usize = 8;
 
 /// Records the most recent symbol and unfinished sequence of a segment
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 struct MemoryHead {
     /// Previous symbol, to compare with the current symbol
     pub previous: Symbol,
@@ -24,12 +37,99 @@ impl MemoryHead {
                 label: 0,
                 content: "start".to_string(),
                 length: 0,
+                span: Span::default(),
+                frames: Span::default(),
             },
             ongoing: Vec::new(),
         }
     }
 }
 
+/// Summary of the symbols that have been forgotten from the episodic memory
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Summary {
+    /// Number of symbols forgotten
+    symbols: usize,
+    /// Total number of subordinate symbols subtended by the forgotten symbols
+    length: usize,
+}
+
+/// Record of a finished segment of the episodic memory
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Segment {
+    /// Position of the first symbol of the segment among all symbols seen
+    pub start: usize,
+    /// Number of symbols in the segment
+    pub symbols: usize,
+    /// Total number of subordinate symbols subtended by the segment
+    pub length: usize,
+    /// Positions of the frames of the source subtended by the segment
+    pub frames: Span,
+}
+
+impl Segment {
+    /// Returns the record of a segment of the given symbols
+    ///
+    /// # Arguments
+    /// * `start` - position of the first symbol among all symbols seen
+    /// * `symbols` - symbols of the segment, in order
+    ///
+    fn new(start: usize, symbols: &[Symbol]) -> Segment {
+        Segment {
+            start,
+            symbols: symbols.len(),
+            length: symbols.iter().map(|symbol| symbol.length).sum(),
+            frames: symbols.iter()
+                .map(|symbol| symbol.frames)
+                .reduce(|a, b| a.join(&b))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Position of a read-only perception in a stream of spectra, holding the
+/// state that perceiving would otherwise change in the dimension
+#[derive(Clone, Debug)]
+pub struct Cursor {
+    /// Most recent labels, which form the context of the next label
+    context: Vec<Label>,
+    /// The most recent symbol and the unfinished segment
+    head: MemoryHead,
+    /// Information content of the recent labels, current last
+    information: Vec<Information>,
+    /// Number of symbols seen, including those only observed
+    position: usize,
+}
+
+/// What a dimension perceives of a spectrum without learning from it
+#[derive(Clone, Debug)]
+pub struct Observation {
+    /// Label of the existing category nearest to the perceived concept
+    pub category: Label,
+    /// Whether the concept is a member of no category, so would begin a new one
+    pub novel: bool,
+    /// Probability of each known label given the preceding labels,
+    /// from most to least probable, as predicted before the perception
+    pub prediction: Vec<(Label, f64)>,
+    /// Information content of the category given the preceding labels
+    pub information: Information,
+    /// Record of the segment finished by the perception, if any
+    pub segment: Option<Segment>,
+    /// Abstraction of the finished segment for the superior level, if any
+    pub superior: Option<Spectrum>,
+}
+
+/// Running totals of what a dimension has learned since it was created
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Counters {
+    /// Number of spectra perceived while learning
+    pub perceived: usize,
+    /// Number of concepts created, whether perceived or split from another
+    pub concepts: usize,
+    /// Number of segments abstracted for the superior level
+    pub segments: usize,
+}
+
 /// Records the previously seen symbols in a given dimension
 #[derive(Serialize, Deserialize, Debug)]
 struct EpisodicMemory {
@@ -37,14 +137,27 @@ struct EpisodicMemory {
     sequence: Vec<Symbol>,
     /// The most recent symbol and the unfinished segment
     head: MemoryHead,
+    /// Maximum number of symbols kept in the sequence (0 for unbounded)
+    capacity: usize,
+    /// Summary of the oldest symbols dropped from the sequence
+    forgotten: Summary,
+    /// Finished segments, oldest first, of which some symbols are remembered
+    segments: Vec<Segment>,
 }
 
 impl EpisodicMemory {
     /// Returns an empty episodic memory
-    fn new() -> EpisodicMemory {
+    ///
+    /// # Arguments
+    /// * `capacity` - maximum number of symbols to keep (0 for unbounded)
+    ///
+    fn new(capacity: usize) -> EpisodicMemory {
         EpisodicMemory {
             sequence: Vec::new(),
             head: MemoryHead::new(),
+            capacity,
+            forgotten: Summary::default(),
+            segments: Vec::new(),
         }
     }
 
@@ -55,9 +168,80 @@ impl EpisodicMemory {
     ///
     fn update(&mut self, symbol: Symbol) {
         self.sequence.push(symbol.clone());
+        self.advance(symbol);
+        if self.capacity > 0 && self.sequence.len() > self.capacity {
+            let excess = self.sequence.len() - self.capacity;
+            for symbol in self.sequence.drain(..excess) {
+                self.forgotten.symbols += 1;
+                self.forgotten.length += symbol.length;
+            }
+            let forgotten = self.forgotten.symbols;
+            self.segments.retain(|segment| segment.start + segment.symbols > forgotten);
+        }
+    }
+
+    /// Updates the head with the given symbol without remembering it
+    ///
+    /// # Arguments
+    /// * `symbol` - The new symbol of the unfinished segment
+    ///
+    fn advance(&mut self, symbol: Symbol) {
         self.head.ongoing.push(symbol.clone());
         self.head.previous = symbol;
     }
+
+    /// Finishes the unfinished segment, recording it and starting a new one.
+    /// Returns the record of the finished segment.
+    fn chop(&mut self) -> Segment {
+        let segment = self.cut();
+        self.segments.push(segment.clone());
+        segment
+    }
+
+    /// Finishes the unfinished segment without recording it, and starts a
+    /// new one. Returns the finished segment.
+    fn cut(&mut self) -> Segment {
+        let ongoing = std::mem::take(&mut self.head.ongoing);
+        Segment::new(self.seen().saturating_sub(ongoing.len()), &ongoing)
+    }
+
+    /// Returns the number of symbols seen, remembered or forgotten
+    fn seen(&self) -> usize {
+        self.forgotten.symbols + self.sequence.len()
+    }
+
+    /// Returns the remembered symbols, oldest first, with their positions
+    /// among all symbols seen
+    fn symbols(&self) -> impl Iterator<Item = (usize, &Symbol)> {
+        let forgotten = self.forgotten.symbols;
+        self.sequence.iter()
+            .enumerate()
+            .map(move |(i, symbol)| (forgotten + i, symbol))
+    }
+
+    /// Returns the labels of the symbols in the unfinished segment and the
+    /// previous symbol, which must remain in the semantic memory
+    fn active(&self) -> Vec<Label> {
+        self.head.ongoing.iter()
+            .chain(std::iter::once(&self.head.previous))
+            .map(|symbol| symbol.label)
+            .collect()
+    }
+
+    /// Replaces the label of every symbol with the given label by another
+    ///
+    /// # Arguments
+    /// * `from` - label to replace
+    /// * `into` - label to replace it with
+    ///
+    fn relabel(&mut self, from: &Label, into: &Label) {
+        let symbols = self.sequence.iter_mut()
+            .chain(self.head.ongoing.iter_mut())
+            .chain(std::iter::once(&mut self.head.previous));
+        for symbol in symbols.filter(|symbol| symbol.label == *from) {
+            symbol.label = *into;
+        }
+    }
 }
 
 /// The conceptual space of a dimension where the concepts live
@@ -65,28 +249,251 @@ impl EpisodicMemory {
 struct SemanticMemory {
     /// Map of an identifying label to the concept representation
     space: HashMap<Label, Concept>,
+    /// Most recent points assigned to each concept, used to detect bimodality
+    exemplars: HashMap<Label, Vec<Vector>>,
+    /// Maximum number of exemplars kept for each concept
+    capacity: usize,
+    /// Most recent label given to a new concept, so that labels are never
+    /// repeated and are the same on every run
+    #[serde(default)]
+    last: Label,
 }
 
 impl SemanticMemory {
     /// Returns an empty semantic memory
-    fn new() -> SemanticMemory {
+    ///
+    /// # Arguments
+    /// * `capacity` - number of exemplars to keep for each concept
+    ///
+    fn new(capacity: usize) -> SemanticMemory {
         SemanticMemory {
             space: HashMap::new(),
+            exemplars: HashMap::new(),
+            capacity,
+            last: 0,
         }
     }
 
+    /// Returns the label following the most recent label given to a new
+    /// concept that is not in the space, without giving it
+    fn peek(&self) -> Label {
+        (self.last + 1..).find(|label| !self.space.contains_key(label)).unwrap()
+    }
+
+    /// Gives a label to a new concept, following the most recent one
+    fn label(&mut self) -> Label {
+        self.last = self.peek();
+        self.last
+    }
+
     /// Inserts the concept at the given category label and updates accordingly
     ///
     /// # Arguments
     /// * `category` - category label of where to insert the given concept
     /// * `concept` - instance of a concept to insert and update with
     ///
-    fn update(&mut self, category: &Label, concept: Concept, count: usize) {
-        let c = self.space.entry(*category).or_insert(concept.clone());
-        c.update(concept, count);
+    fn update(&mut self, category: &Label, concept: Concept) {
+        if self.capacity > 0 {
+            let exemplars = self.exemplars.entry(*category).or_default();
+            let point = concept.location.centroid.clone();
+            if exemplars.len() < self.capacity {
+                exemplars.push(point);
+            } else {
+                let count = self.space.get(category).map_or(0, |c| c.moments.count);
+                exemplars[count % self.capacity] = point;
+            }
+        }
+        let c = self.space.entry(*category).or_insert_with(|| concept.clone());
+        c.update(concept);
+    }
+
+    /// Forgets the concept with the given label and its exemplars
+    ///
+    /// # Arguments
+    /// * `label` - label of the concept to forget
+    ///
+    fn remove(&mut self, label: &Label) {
+        self.space.remove(label);
+        self.exemplars.remove(label);
+    }
+
+    /// Repeatedly merges the pair of concepts that overlap the most, as long
+    /// as their overlap is at least the threshold. The concept seen more often
+    /// absorbs the other. Returns the (absorbed, absorbing) label pairs in order.
+    ///
+    /// # Arguments
+    /// * `threshold` - minimum Bhattacharyya coefficient for a merge
+    ///
+    fn merge(&mut self, threshold: f64) -> Vec<(Label, Label)> {
+        let mut labels: Vec<Label> = self.space.keys().copied().collect();
+        labels.sort();
+
+        // Only a merge changes an overlap, so the pairs that may merge are
+        // found once and then only those of the absorbing concept are redone
+        let mut candidates: BTreeMap<(Label, Label), f64> = BTreeMap::new();
+        for (i, a) in labels.iter().enumerate() {
+            for b in labels[i + 1..].iter() {
+                self.candidate(&mut candidates, *a, *b, threshold);
+            }
+        }
+        let mut merges = Vec::new();
+        loop {
+            let best = candidates.iter().max_by(|(_, x), (_, y)| x.total_cmp(y));
+            let (a, b) = match best {
+                Some((pair, _)) => *pair,
+                None => return merges,
+            };
+            let (from, into) = if self.space[&a].moments.count >= self.space[&b].moments.count {
+                (b, a)
+            } else {
+                (a, b)
+            };
+            let absorbed = self.space.remove(&from).unwrap();
+            self.space.get_mut(&into).unwrap().merge(&absorbed);
+            if let Some(exemplars) = self.exemplars.remove(&from) {
+                let kept = self.exemplars.entry(into).or_default();
+                kept.extend(exemplars);
+                let excess = kept.len().saturating_sub(self.capacity);
+                kept.drain(..excess);
+            }
+            merges.push((from, into));
+
+            candidates.retain(|(a, b), _| ![from, into].contains(a) && ![from, into].contains(b));
+            labels.retain(|label| *label != from);
+            for label in labels.iter().filter(|label| **label != into) {
+                self.candidate(&mut candidates, into.min(*label), into.max(*label), threshold);
+            }
+        }
+    }
+
+    /// Records the overlap of the pair of concepts if they may merge, i.e.
+    /// their overlap is at least the threshold
+    ///
+    /// # Arguments
+    /// * `candidates` - overlap of each pair that may merge, lower label first
+    /// * `a` - lower label of the pair
+    /// * `b` - higher label of the pair
+    /// * `threshold` - minimum Bhattacharyya coefficient for a merge
+    ///
+    fn candidate(&self, candidates: &mut BTreeMap<(Label, Label), f64>, a: Label, b: Label, threshold: f64) {
+        let overlap = self.space[&a].overlap(&self.space[&b]);
+        if overlap >= threshold {
+            candidates.insert((a, b), overlap);
+        }
+    }
+
+    /// Splits each concept whose exemplars form two well-separated clusters,
+    /// i.e. their Ashman's D along the line between the clusters is at least
+    /// the threshold. The larger cluster keeps the label of the concept, and
+    /// the concepts the original stood for are shared between the clusters
+    /// in proportion to their number of exemplars. Returns the (original,
+    /// new) label pairs with the share of the new label.
+    ///
+    /// # Arguments
+    /// * `threshold` - minimum Ashman's D for a split
+    /// * `exclude` - labels of concepts not to split
+    ///
+    fn split(&mut self, threshold: f64, exclude: &[Label]) -> Vec<(Label, Label, f64)> {
+        let mut labels: Vec<Label> = self.exemplars.keys()
+            .filter(|label| !exclude.contains(label))
+            .copied()
+            .collect();
+        labels.sort();
+        let mut splits = Vec::new();
+        for label in labels {
+            let count = self.space[&label].moments.count;
+            let (larger, smaller) = match bisect(&self.exemplars[&label]) {
+                Some((larger, smaller, separation)) if separation >= threshold && count > 1 => {
+                    (larger, smaller)
+                }
+                _ => continue,
+            };
+            let share = smaller.len() as f64 / (larger.len() + smaller.len()) as f64;
+            let moved = ((count as f64 * share).round() as usize).clamp(1, count - 1);
+            let new = self.label();
+            let original = self.space.remove(&label).unwrap();
+            for (label, cluster, count) in [(label, larger, count - moved), (new, smaller, moved)] {
+                self.space.insert(label, original.part(label, &cluster, count));
+                self.exemplars.insert(label, cluster);
+            }
+            splits.push((label, new, share));
+        }
+        splits
     }
 }
 
+/// Returns the mean of the given points
+fn mean(points: &[Vector]) -> Vector {
+    let mut sum = Vector::zeros(points[0].len());
+    for point in points {
+        sum += point;
+    }
+    sum / points.len() as f64
+}
+
+/// Splits the points into two clusters by 2-means, seeded with the two points
+/// farthest apart. Returns the larger and the smaller cluster, along with
+/// their Ashman's D along the line between their means.
+///
+/// # Arguments
+/// * `points` - points to split
+///
+fn bisect(points: &[Vector]) -> Option<(Vec<Vector>, Vec<Vector>, f64)> {
+    if points.len() < 4 {
+        return None;
+    }
+    let distance = |a: &Vector, b: &Vector| (a - b).norm();
+    let centre = mean(points);
+    let first = points.iter()
+        .max_by(|a, b| distance(a, &centre).total_cmp(&distance(b, &centre)))?;
+    let second = points.iter()
+        .max_by(|a, b| distance(a, first).total_cmp(&distance(b, first)))?;
+    let mut means = (first.clone(), second.clone());
+    let mut clusters: (Vec<Vector>, Vec<Vector>) = (Vec::new(), Vec::new());
+    for _ in 0..BISECTIONS {
+        let (left, right): (Vec<Vector>, Vec<Vector>) = points.iter().cloned()
+            .partition(|p| distance(p, &means.0) <= distance(p, &means.1));
+        if left.len() < 2 || right.len() < 2 {
+            return None;
+        }
+        means = (mean(&left), mean(&right));
+        clusters = (left, right);
+    }
+
+    // Project onto the line between the means to compare spread and separation
+    let axis = &means.1 - &means.0;
+    let length = axis.norm();
+    if length == 0.0 {
+        return None;
+    }
+    let project = |p: &Vector| -> f64 {
+        axis.iter().zip(p.iter()).map(|(a, x)| a.conj() * x).sum::<c64>().re / length
+    };
+    let variance = |cluster: &Vec<Vector>| {
+        let values: Vec<f64> = cluster.iter().map(project).collect();
+        let m = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / values.len() as f64
+    };
+    let spread = ((variance(&clusters.0) + variance(&clusters.1)) / 2.0).sqrt();
+    let separation = if spread == 0.0 { f64::INFINITY } else { length / spread };
+    let (left, right) = clusters;
+    if left.len() >= right.len() {
+        Some((left, right, separation))
+    } else {
+        Some((right, left, separation))
+    }
+}
+
+/// Concepts merged and split by a consolidation of a dimension
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Consolidation {
+    /// Label of each absorbed concept with that of the concept absorbing it
+    pub merges: Vec<(Label, Label)>,
+    /// Label of each split concept with that of the concept split from it
+    /// and its share of the counts
+    pub splits: Vec<(Label, Label, f64)>,
+}
+
 /// The dimension at a given level of abstraction consisting of the dual memory
 /// and statistics of the constituent labels
 #[derive(Serialize, Deserialize, Debug)]
@@ -97,14 +504,62 @@ pub struct Dimension {
     radius_scale: f64,
     /// Number of real and virtual concepts in a given trajectory
     resolution: u16,
+    /// Method of sampling a trajectory to resolution concepts
+    #[serde(default)]
+    interpolation: Interpolation,
+    /// Transform of the trajectories abstracted for the superior level
+    #[serde(default)]
+    transformation: Transformation,
+    /// Trajectories collected to fit the transform, until it is fitted
+    #[serde(default)]
+    samples: Vec<Signal>,
+    /// Reduction of the coefficients, recorded to expand them again
+    #[serde(default)]
+    reduction: Reducer,
+    /// Metric deciding membership of a concept in a category
+    metric: Distance,
+    /// Structure of the covariance of each concept
+    structure: Structure,
+    /// Method of assigning a concept to a category
+    categorizer: Categorizer,
+    /// Posterior over the categories of the most recent concept, if soft
+    #[serde(skip)]
+    posterior: Option<Posterior>,
+    /// Number of perceived concepts between consolidations (0 for never)
+    consolidation_period: usize,
+    /// Minimum overlap of two concepts for them to merge
+    merge_threshold: f64,
+    /// Minimum separation of the exemplars of a concept for it to split
+    split_threshold: f64,
+    /// Number of perceived concepts between forgetting (0 for never)
+    forgetting_period: usize,
+    /// Factor by which the counts of the markov models decay when forgetting
+    decay: f64,
+    /// Concepts seen fewer times than this after decay are forgotten
+    prune_below: usize,
     /// Memory of previously seen symbols
     episodic: EpisodicMemory,
     /// Space of concepts
     semantic: SemanticMemory,
     /// Counts the number of times a label is seen
     unigram: UnigramModel<Label>,
-    /// Counts the number of times pairs of labels are seen
-    bigram: BigramModel<Label>,
+    /// Counts the number of times sequences of labels are seen
+    ngram: NGramModel<Label>,
+    /// Method of estimating the probability of the next label
+    smoothing: Smoothing,
+    /// Information content of each perceived label, bounded like the history
+    information: Vec<Information>,
+    /// Decides where segments begin
+    segmenter: Segmentation,
+    /// Totals of the spectra perceived, concepts created, and segments abstracted
+    #[serde(default)]
+    counters: Counters,
+    /// Whether perception leaves the concepts, counts, and history unchanged
+    #[serde(default)]
+    frozen: bool,
+    /// Position of the perception since the dimension was frozen
+    #[serde(skip)]
+    cursor: Option<Cursor>,
 }
 
 impl Dimension {
@@ -112,18 +567,37 @@ impl Dimension {
     ///
     /// # Arguments
     /// * `level` - index of depth in memory hierarchy
-    /// * `radius_scale` - scale of initial radius of a concept
-    /// * `resolution` - number of real + virtual points in a segment
+    /// * `config` - configuration for scale, resolution, metric, and structure
     ///
-    pub fn new(level: u16, radius_scale: f64, resolution: u16) -> Dimension {
+    pub fn new(level: u16, config: &Config) -> Dimension {
         Dimension {
             level,
-            radius_scale,
-            resolution,
-            episodic: EpisodicMemory::new(),
-            semantic: SemanticMemory::new(),
+            radius_scale: 10f64.powi(level as i32) * config.radius_scale,
+            resolution: config.resolution,
+            interpolation: config.interpolation,
+            transformation: config.transformation(level),
+            samples: Vec::new(),
+            reduction: config.reduction(level).into(),
+            metric: config.metric(level),
+            structure: config.structure(level),
+            categorizer: config.categorizer,
+            posterior: None,
+            consolidation_period: config.consolidation_period,
+            merge_threshold: config.merge_threshold,
+            split_threshold: config.split_threshold,
+            forgetting_period: config.forgetting_period,
+            decay: config.decay,
+            prune_below: config.prune_below,
+            episodic: EpisodicMemory::new(config.history),
+            semantic: SemanticMemory::new(config.exemplars),
             unigram: UnigramModel::new(),
-            bigram: BigramModel::new(),
+            ngram: NGramModel::new(config.order),
+            smoothing: config.smoothing,
+            information: Vec::new(),
+            segmenter: config.segmenter(level),
+            counters: Counters::default(),
+            frozen: false,
+            cursor: None,
         }
     }
 
@@ -131,54 +605,859 @@ impl Dimension {
     /// Inserts the spectrum (from the subordinate layer) as a symbol/concept,
     /// then categorizes, updates, and segments the resulting memory.
     /// If segmentation occurs, then the abstracted spectrum is returned.
-    pub fn perceive(&mut self, spectrum: Spectrum) -> Option<Spectrum> {
+    /// Once frozen, the spectrum is only observed from where perception left off.
+    /// Fails without perceiving a spectrum unlike the concepts of the dimension
+    /// or of which the trajectories cannot be abstracted.
+    pub fn perceive(&mut self, spectrum: Spectrum) -> Result<Option<Spectrum>, PredictError> {
+        self.step(spectrum, &mut |_: &Event| {})
+    }
+
+    /// Perceives the spectrum as `perceive` does, notifying the observer of
+    /// each event of the perception as it happens
+    ///
+    /// # Arguments
+    /// * `spectrum` - spectrum from the subordinate layer
+    /// * `frame` - index of the frame of the source that led to the spectrum,
+    ///   which is passed to the observer with every event
+    /// * `observer` - notified of the events of the perception
+    ///
+    pub fn perceive_with(&mut self, spectrum: Spectrum, frame: usize, observer: &mut dyn Observer) -> Result<Option<Spectrum>, PredictError> {
+        let level = self.level;
+        self.step(spectrum, &mut |event: &Event| observer.notify(frame, level, event))
+    }
+
+    /// Perceives the spectrum as `perceive` does, passing each event of the
+    /// perception to the closure as it happens
+    ///
+    /// # Arguments
+    /// * `spectrum` - spectrum from the subordinate layer
+    /// * `notify` - receives the events of the perception
+    ///
+    fn step(&mut self, spectrum: Spectrum, notify: &mut dyn FnMut(&Event)) -> Result<Option<Spectrum>, PredictError> {
+        self.accepts(&spectrum)?;
+        notify(&Event::Perceived { spectrum: &spectrum });
+        if self.frozen {
+            let mut cursor = self.cursor.take().unwrap_or_else(|| self.cursor());
+            let observation = self.observe(&mut cursor, spectrum);
+            self.cursor = Some(cursor);
+            let observation = observation?;
+            notify(&Event::Categorized { label: observation.category, information: observation.information });
+            if let (Some(segment), Some(superior)) = (&observation.segment, &observation.superior) {
+                notify(&Event::Segmented { segment, spectrum: superior });
+            }
+            return Ok(observation.superior);
+        }
+
+        // Periodically merge overlapping concepts and split bimodal ones,
+        // counting the spectra perceived since the counts of the models decay
+        let seen = self.counters.perceived;
+        if seen > 0 && self.consolidation_period > 0 && seen % self.consolidation_period == 0 {
+            let consolidation = self.consolidate();
+            for (from, into) in consolidation.merges {
+                notify(&Event::Merged { from, into });
+            }
+            for (original, new, share) in consolidation.splits {
+                notify(&Event::Split { original, concept: &self.semantic.space[&new], share });
+            }
+        }
+
+        // Periodically decay the counts and forget rarely seen concepts
+        if seen > 0 && self.forgetting_period > 0 && seen % self.forgetting_period == 0 {
+            for label in self.forget() {
+                notify(&Event::Forgotten { label });
+            }
+        }
 
         // Create a new symbol/concept with a label
-        let (concept, mut symbol) = gen_concept_symbol(spectrum, self.radius_scale);
+        let label = self.semantic.peek();
+        let (concept, mut symbol) = gen_concept_symbol(label, spectrum, self.radius_scale, self.structure);
 
         // Categorize the concept in the semantic space
-        let category = categorize(&concept, &self.semantic.space, &self.unigram);
+        let category = match self.categorizer {
+            Categorizer::Hard => {
+                categorize(&concept, &self.semantic.space, &self.unigram, &self.metric)
+            }
+            Categorizer::Soft => {
+                let (category, posterior) = categorize_soft(
+                    &concept, &self.semantic.space, &self.unigram, &self.metric);
+                self.posterior = Some(posterior);
+                category
+            }
+        };
         symbol.label = category;
 
-        // Update the markov models of the resulting category
-        self.unigram.increment(&category);
+        // Measure the information content of the category, then update the
+        // markov models and the category with it
+        let information = self.measure(self.ngram.context(), &category);
+        record(&mut self.information, information, self.episodic.capacity);
         let previous = self.episodic.head.previous.label;
-        self.bigram.increment(&previous, &category);
+        self.counters.perceived += 1;
+        notify(&Event::Categorized { label: category, information });
+        if category == label {
+            self.semantic.label();
+            self.counters.concepts += 1;
+            trace!("level {}: new concept {}", self.level, label);
+            notify(&Event::Created { concept: &concept });
+        }
+        self.unigram.increment(&category);
+        self.ngram.update(&category);
+        self.semantic.update(&category, concept);
+        notify(&Event::Updated { concept: &self.semantic.space[&category] });
+
+        // Determine if the current symbol begins a new segment, which it
+        // cannot if the unfinished segment is empty
+        let context = Context {
+            unigram: &self.unigram,
+            ngram: &self.ngram,
+            previous: &previous,
+            current: &category,
+            information: &self.information,
+            length: self.episodic.head.ongoing.len(),
+        };
+        let boundary = !self.episodic.head.ongoing.is_empty() && self.segmenter.segment(&context);
+
+        // Abstract the finished segment to a spectrum for the superior level
+        let trajectory = if boundary { self.trajectory(&self.episodic.head.ongoing) } else { Vec::new() };
+        let superior = if !trajectory.is_empty() {
+            // Interpolate the trajectory of the segment to a signal
+            let signal = interpolate(trajectory, self.resolution, self.interpolation)?;
+            let segment = self.episodic.chop();
+            trace!("level {}: segment of {} symbols from {}", self.level, segment.symbols, segment.start);
+            if self.fit(&signal)? {
+                None
+            } else {
+                self.counters.segments += 1;
+                let superior = self.abstraction(signal, &segment)?;
+                notify(&Event::Segmented { segment: &segment, spectrum: &superior });
+                Some(superior)
+            }
+        } else {
+            None
+        };
+
+        // Update the episodic memory and its head with the current symbol,
+        // which begins the new segment if there was a boundary
+        self.episodic.update(symbol);
+        Ok(superior)
+    }
+
+    /// Perceives the spectrum without changing the dimension, advancing the
+    /// cursor instead. The concept is categorized as the nearest existing
+    /// category, and the observation reports what perceiving would predict,
+    /// measure, and segment. Fails without advancing the cursor if the
+    /// spectrum is unlike the concepts of the dimension.
+    ///
+    /// # Arguments
+    /// * `cursor` - position of the perception, as returned by `cursor`
+    /// * `spectrum` - spectrum from the subordinate layer
+    ///
+    pub fn observe(&self, cursor: &mut Cursor, spectrum: Spectrum) -> Result<Observation, PredictError> {
+        self.accepts(&spectrum)?;
+        let label = self.semantic.peek();
+        let (concept, mut symbol) = gen_concept_symbol(label, spectrum, self.radius_scale, self.structure);
 
-        // Update the category with the new concept
-        self.semantic.update(&category, concept, self.unigram[category]);
+        // Categorize the concept as the nearest existing category, noting
+        // whether perceiving it would have begun a new one
+        let novel = categorize(&concept, &self.semantic.space, &self.unigram, &self.metric) == concept.label;
+        let category = nearest(&concept, &self.semantic.space, &self.metric).unwrap_or(concept.label);
+        symbol.label = category;
+
+        // Predict and measure the category given the labels before it
+        let prediction = self.prediction(&cursor.context);
+        let information = self.measure(&cursor.context, &category);
+        record(&mut cursor.information, information, self.episodic.capacity);
+        cursor.context.push(category);
+        let excess = cursor.context.len().saturating_sub(self.ngram.order() - 1);
+        cursor.context.drain(..excess);
 
-        // Determine if segmentation should occur at this symbol
-        if segment(&self.unigram, &previous, &category) {
+        // Determine if the current symbol would begin a new segment
+        let previous = cursor.head.previous.label;
+        let context = Context {
+            unigram: &self.unigram,
+            ngram: &self.ngram,
+            previous: &previous,
+            current: &category,
+            information: &cursor.information,
+            length: cursor.head.ongoing.len(),
+        };
+        let boundary = !cursor.head.ongoing.is_empty() && self.segmenter.segment(&context);
+        let trajectory = if boundary { self.trajectory(&cursor.head.ongoing) } else { Vec::new() };
+        let (segment, superior) = if !trajectory.is_empty() {
+            let signal = interpolate(trajectory, self.resolution, self.interpolation)?;
+            let ongoing = std::mem::take(&mut cursor.head.ongoing);
+            let segment = Segment::new(cursor.position - ongoing.len(), &ongoing);
+            let superior = match self.transformation {
+                Transformation::LearnedPca { .. } => None,
+                _ => Some(self.abstraction(signal, &segment)?),
+            };
+            (Some(segment), superior)
+        } else {
+            (None, None)
+        };
 
-            // Convert segment to trajectory and interpolate to a signal
-            let trajectory = self.current_trajectory();
-            let signal = interpolate(trajectory, self.resolution);
+        cursor.head.ongoing.push(symbol.clone());
+        cursor.head.previous = symbol;
+        cursor.position += 1;
+        Ok(Observation { category, novel, prediction, information, segment, superior })
+    }
 
-            // Abstract the trajectory of the segment to a spectrum
-            let superior = transform(signal);
-            return Some(superior);
+    /// Returns a cursor from which to observe where perception left off
+    pub fn cursor(&self) -> Cursor {
+        Cursor {
+            context: self.ngram.context().to_vec(),
+            head: self.episodic.head.clone(),
+            information: self.information.clone(),
+            position: self.episodic.seen(),
         }
+    }
 
-        // Update the episodic memory and its head
-        self.episodic.update(symbol);
-        None
+    /// Stops the dimension from learning. Later perception observes each
+    /// spectrum from where perception left off, leaving the concepts, counts,
+    /// and history unchanged.
+    pub fn freeze(&mut self) {
+        self.frozen = true;
     }
 
-    /// Return a list of vector-length pairs corresponding to the current segment
-    fn current_trajectory(&self) -> Vec<(Vector, usize)> {
-        self.episodic.head.ongoing.iter()
-            .map(|symbol| (symbol.label, symbol.length))
-            .map(|(label, length)| (self.semantic.space.get(&label).unwrap(), length))
-            .map(|(concept, length)| (concept.location.centroid.clone(), length))
+    /// Returns the label of the most recently perceived symbol
+    pub fn last(&self) -> Label {
+        self.cursor.as_ref().map_or(&self.episodic.head, |cursor| &cursor.head).previous.label
+    }
+
+    /// Merges concepts whose regions overlap beyond the merge threshold and
+    /// splits concepts whose exemplars are bimodal. Symbols and counts of a
+    /// merged concept are relabeled to the concept that absorbed it. The
+    /// symbols of a split concept remain with the cluster that keeps its
+    /// label, whereas its counts are shared like its exemplars.
+    /// A concept that absorbed another is not split in the same consolidation.
+    /// Returns the concepts merged and split.
+    pub fn consolidate(&mut self) -> Consolidation {
+        let merges = self.semantic.merge(self.merge_threshold);
+        for (from, into) in merges.iter() {
+            self.episodic.relabel(from, into);
+            self.unigram.relabel(from, into);
+            self.ngram.relabel(from, into);
+        }
+        let merged: Vec<Label> = merges.iter().map(|(_, into)| *into).collect();
+        let splits = self.semantic.split(self.split_threshold, &merged);
+        for (original, new, share) in splits.iter() {
+            self.unigram.split(original, new, *share);
+            self.ngram.split(original, new, *share);
+        }
+        self.counters.concepts += splits.len();
+        if !merges.is_empty() || !splits.is_empty() {
+            debug!("level {}: merged {:?}, split {:?}", self.level, merges, splits);
+        }
+        Consolidation { merges, splits }
+    }
+
+    /// Decays the counts of the markov models and forgets the concepts seen
+    /// fewer than `prune_below` times since, unless they are in the unfinished
+    /// segment. The episodic memory bounds itself as symbols are added.
+    /// Returns the labels of the forgotten concepts.
+    pub fn forget(&mut self) -> Vec<Label> {
+        self.unigram.decay(self.decay);
+        self.ngram.decay(self.decay);
+        let active = self.episodic.active();
+        let rare: Vec<Label> = self.semantic.space.keys()
+            .filter(|label| self.unigram.count(label) < self.prune_below)
+            .filter(|label| !active.contains(label))
+            .copied()
+            .collect();
+        for label in rare.iter() {
+            self.semantic.remove(label);
+            self.unigram.remove(label);
+            self.ngram.remove(label);
+        }
+        if !rare.is_empty() {
+            debug!("level {}: forgot {} rare concepts", self.level, rare.len());
+        }
+        rare
+    }
+
+    /// Returns the posterior over the categories of the most recently
+    /// perceived concept, if this dimension categorizes softly
+    pub fn posterior(&self) -> Option<&Posterior> {
+        self.posterior.as_ref()
+    }
+
+    /// Returns the level of abstraction of this dimension
+    pub fn level(&self) -> u16 {
+        self.level
+    }
+
+    /// Returns the totals of the spectra perceived, concepts created, and
+    /// segments abstracted by this dimension
+    pub fn counters(&self) -> Counters {
+        self.counters
+    }
+
+    /// Returns the remembered symbols, oldest first, with their positions
+    /// among all symbols seen by this dimension
+    pub fn symbols(&self) -> impl Iterator<Item = (usize, &Symbol)> {
+        self.episodic.symbols()
+    }
+
+    /// Returns the concepts of this dimension, in no particular order
+    pub fn concepts(&self) -> impl Iterator<Item = &Concept> {
+        self.semantic.space.values()
+    }
+
+    /// Returns the finished segments, oldest first, of which the history
+    /// still holds some symbols
+    pub fn segments(&self) -> &[Segment] {
+        &self.episodic.segments
+    }
+
+    /// Returns the information content of each perceived label, oldest first
+    pub fn information(&self) -> &[Information] {
+        &self.information
+    }
+
+    /// Returns the information content of the label given the preceding labels
+    ///
+    /// # Arguments
+    /// * `context` - preceding labels, most recent last
+    /// * `label` - label to measure
+    ///
+    fn measure(&self, context: &[Label], label: &Label) -> Information {
+        Information::measure(&self.ngram, context, label, self.smoothing)
+    }
+
+    /// Returns the smoothed probability of each known label being perceived
+    /// next, given the most recent labels, from most to least probable.
+    /// The remaining probability is that of a label not yet seen.
+    pub fn predict(&self) -> Vec<(Label, f64)> {
+        match &self.cursor {
+            Some(cursor) => self.prediction(&cursor.context),
+            None => self.prediction(self.ngram.context()),
+        }
+    }
+
+    /// Returns the smoothed probability of each known label following the
+    /// given labels, from most to least probable
+    ///
+    /// # Arguments
+    /// * `context` - preceding labels, most recent last
+    ///
+    fn prediction(&self, context: &[Label]) -> Vec<(Label, f64)> {
+        let mut prediction = self.ngram.probabilities(context, self.smoothing);
+        prediction.sort_by(|a, b| b.1.total_cmp(&a.1));
+        prediction
+    }
+
+    /// Collects the signal of a finished segment if the transform is yet to
+    /// be fitted, fitting it once enough signals are collected. Returns
+    /// whether the signal was collected, in which case it is not abstracted.
+    ///
+    /// # Arguments
+    /// * `signal` - interpolated trajectory of the segment
+    ///
+    fn fit(&mut self, signal: &Signal) -> Result<bool, PredictError> {
+        if let Transformation::LearnedPca { components, trajectories } = self.transformation {
+            self.samples.push(signal.clone());
+            if self.samples.len() >= trajectories {
+                let pca = Pca::fit(&std::mem::take(&mut self.samples), components)?;
+                debug!("level {}: fitted {} principal axes", self.level, pca.basis.ncols());
+                self.transformation = Transformation::Pca(pca);
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Returns the spectrum abstracting the signal of a finished segment,
+    /// spanning its symbols and frames
+    ///
+    /// # Arguments
+    /// * `signal` - interpolated trajectory of the segment
+    /// * `segment` - record of the segment
+    ///
+    fn abstraction(&self, signal: Signal, segment: &Segment) -> Result<Spectrum, PredictError> {
+        let mut superior = transform(signal, &self.transformation, &self.reduction)?;
+        superior.span = Span::new(segment.start, segment.start + segment.symbols);
+        superior.frames = segment.frames;
+        Ok(superior)
+    }
+
+    /// Returns the trajectory of concepts of this dimension that a spectrum
+    /// abstracted by it represents, one row per sample, if any concepts are
+    /// known from which to tell the length of their vectors. Fails if the
+    /// spectrum cannot have been abstracted by this dimension.
+    ///
+    /// # Arguments
+    /// * `spectrum` - spectrum abstracted by this dimension
+    ///
+    pub fn invert(&self, spectrum: &Spectrum) -> Result<Option<Signal>, PredictError> {
+        match self.width() {
+            Some(width) => {
+                let shape = (self.resolution as usize, width);
+                invert(spectrum, shape, &self.transformation, &self.reduction).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the number of components of the concepts of this dimension,
+    /// if any concepts are known from which to tell
+    fn width(&self) -> Option<usize> {
+        self.semantic.space.values().next().map(|concept| concept.location.centroid.len())
+    }
+
+    /// Fails unless the spectrum has as many components as the concepts of
+    /// this dimension, or no concepts are known yet, and the trajectories of
+    /// such concepts can be abstracted, so that perception fails before
+    /// it changes anything rather than once a segment ends
+    ///
+    /// # Arguments
+    /// * `spectrum` - spectrum from the subordinate layer
+    ///
+    fn accepts(&self, spectrum: &Spectrum) -> Result<(), PredictError> {
+        let length = spectrum.point.len();
+        if let Some(width) = self.width().filter(|width| *width != length) {
+            return Err(PredictError::Shape(format!(
+                "level {} perceives spectra of {} components, not {}", self.level, width, length)));
+        }
+        if self.resolution == 0 {
+            return Err(PredictError::Configuration(format!(
+                "level {} samples trajectories at no resolution", self.level)));
+        }
+        let fitted = !matches!(self.transformation, Transformation::LearnedPca { .. });
+        if fitted && self.transformation.width(length).is_none() {
+            return Err(PredictError::Shape(format!(
+                "level {} cannot transform trajectories of {} components", self.level, length)));
+        }
+        Ok(())
+    }
+
+    /// Fails if the memory of this dimension is inconsistent, as it may be
+    /// when restored from a damaged file: every concept must be filed under
+    /// its own label, all concepts must have as many components, and every
+    /// concept with exemplars must exist
+    pub fn verify(&self) -> Result<(), PredictError> {
+        let corrupted = |message: String| Err(PredictError::Corrupted(format!("level {}: {}", self.level, message)));
+        let width = self.width();
+        for (label, concept) in self.semantic.space.iter() {
+            if concept.label != *label {
+                return corrupted(format!("concept {} is filed under {}", concept.label, label));
+            }
+            if Some(concept.location.centroid.len()) != width {
+                return corrupted(format!("concept {} has {} components", label, concept.location.centroid.len()));
+            }
+        }
+        if let Some(label) = self.semantic.exemplars.keys().find(|label| !self.semantic.space.contains_key(label)) {
+            return corrupted(format!("exemplars of unknown concept {}", label));
+        }
+        Ok(())
+    }
+
+    /// Return a list of vector-length pairs corresponding to the given segment,
+    /// leaving out symbols without a concept, as when observing before any
+    /// concept was learned
+    ///
+    /// # Arguments
+    /// * `ongoing` - symbols of the unfinished segment
+    ///
+    fn trajectory(&self, ongoing: &[Symbol]) -> Vec<(Vector, usize)> {
+        ongoing.iter()
+            .filter_map(|symbol| self.semantic.space.get(&symbol.label)
+                .map(|concept| (concept.location.centroid.clone(), symbol.length)))
             .collect()
     }
 }
 
+/// Appends the information content to the record of it, keeping at most the
+/// given number of the most recent (0 for unbounded)
+///
+/// # Arguments
+/// * `information` - record of the information content, oldest first
+/// * `latest` - information content to append
+/// * `capacity` - maximum number to keep (0 for unbounded)
+///
+fn record(information: &mut Vec<Information>, latest: Information, capacity: usize) {
+    information.push(latest);
+    if capacity > 0 && information.len() > capacity {
+        information.drain(..information.len() - capacity);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::reduction::Reduction;
+
+    fn vector(value: f64) -> Vector {
+        (0..4).map(|i| c64::new(value + 0.01 * i as f64, 0.0)).collect()
+    }
+
+    fn insert(semantic: &mut SemanticMemory, label: Label, value: f64) {
+        let concept = Concept::new(label, vector(value), 1.0, Structure::Diagonal);
+        semantic.update(&label, concept);
+    }
 
     #[test]
-    fn test() {}
+    fn test_merge() {
+        let mut semantic = SemanticMemory::new(4);
+        insert(&mut semantic, 1, 0.0);
+        insert(&mut semantic, 1, 0.1);
+        insert(&mut semantic, 2, 0.05);
+        insert(&mut semantic, 3, 10.0);
+        assert_eq!(semantic.merge(0.5), vec![(2, 1)]);
+        assert_eq!(semantic.space.len(), 2);
+        assert_eq!(semantic.space[&1].moments.count, 3);
+        assert_eq!(semantic.exemplars[&1].len(), 3);
+        assert!(semantic.merge(0.5).is_empty());
+    }
+
+    #[test]
+    fn test_split() {
+        let mut semantic = SemanticMemory::new(16);
+        for i in 0..8 {
+            insert(&mut semantic, 1, 0.05 * i as f64);
+            insert(&mut semantic, 1, 10.0 + 0.05 * i as f64);
+        }
+        insert(&mut semantic, 2, 5.0);
+        assert!(semantic.split(4.0, &[1]).is_empty());
+        let splits = semantic.split(4.0, &[]);
+        assert_eq!(splits.len(), 1);
+        let (original, new, share) = splits[0];
+        assert_eq!((original, share), (1, 0.5));
+        assert_eq!(semantic.space.len(), 3);
+        let first = semantic.space[&original].location.centroid[0].re;
+        let second = semantic.space[&new].location.centroid[0].re;
+        assert!((first - second).abs() > 9.0);
+        assert_eq!(semantic.space[&original].moments.count, 8);
+        assert_eq!(semantic.space[&new].moments.count, 8);
+        assert!(semantic.split(4.0, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_consolidate_split() {
+        let mut config = Config::default().unwrap();
+        config.radius_scale = 1000.0;
+        config.consolidation_period = 0;
+        let mut dimension = Dimension::new(0, &config);
+        for i in 0..12 {
+            let value = if i % 4 < 2 { 0.0 } else { 10.0 };
+            dimension.perceive(Spectrum::point(vector(value + 0.01 * i as f64))).unwrap();
+        }
+        assert_eq!(dimension.semantic.space.len(), 1);
+        let original = dimension.episodic.sequence[0].label;
+        dimension.consolidate();
+        assert_eq!(dimension.semantic.space.len(), 2);
+        assert_eq!(dimension.counters().concepts, 2);
+
+        // The new concept stands for its share of the concepts and counts
+        let new = *dimension.semantic.space.keys().find(|label| **label != original).unwrap();
+        assert_eq!(dimension.semantic.space[&original].moments.count, 6);
+        assert_eq!(dimension.semantic.space[&new].moments.count, 6);
+        assert_eq!((dimension.unigram.count(&original), dimension.unigram.count(&new)), (6, 6));
+        assert_eq!((dimension.ngram.count(&[original]), dimension.ngram.count(&[new])), (6, 6));
+        assert_eq!(dimension.unigram.total(), 12);
+
+        // So it is not forgotten as soon as it appears
+        dimension.decay = 1.0;
+        dimension.forget();
+        assert_eq!(dimension.semantic.space.len(), 2);
+    }
+
+    #[test]
+    fn test_consolidate() {
+        let config = Config::default().unwrap();
+        let mut dimension = Dimension::new(0, &config);
+        for value in &[0.0, 100.0, 0.0, 100.0] {
+            dimension.perceive(Spectrum::point(vector(*value))).unwrap();
+        }
+        assert_eq!(dimension.semantic.space.len(), 2);
+        dimension.merge_threshold = 0.0;
+        dimension.consolidate();
+        assert_eq!(dimension.semantic.space.len(), 1);
+        let label = *dimension.semantic.space.keys().next().unwrap();
+        assert_eq!(dimension.unigram.count(&label), 4);
+        assert_eq!(dimension.unigram.distinct(), 1);
+        assert!(dimension.episodic.sequence.iter().all(|symbol| symbol.label == label));
+    }
+
+    #[test]
+    fn test_predict() {
+        let config = Config::default().unwrap();
+        let mut dimension = Dimension::new(0, &config);
+        for value in &[0.0, 100.0, 0.0, 100.0] {
+            dimension.perceive(Spectrum::point(vector(*value))).unwrap();
+        }
+        let prediction = dimension.predict();
+        assert_eq!(prediction.len(), 2);
+        assert_eq!(prediction[0].0, dimension.episodic.sequence[0].label);
+        assert!(prediction[0].1 > prediction[1].1);
+        assert!(prediction.iter().map(|(_, p)| p).sum::<f64>() < 1.0);
+    }
+
+    #[test]
+    fn test_information() {
+        let config = Config::default().unwrap();
+        let mut dimension = Dimension::new(0, &config);
+        for value in &[0.0, 100.0, 0.0, 100.0, 0.0] {
+            dimension.perceive(Spectrum::point(vector(*value))).unwrap();
+        }
+        let information = dimension.information();
+        assert_eq!(information.len(), 5);
+        // The first label is the only possibility, the second is novel
+        assert!(information[0].surprisal.abs() < 1e-9);
+        assert!(information[1].surprisal > 0.0);
+        // Once the alternation is learned, the next label is less surprising
+        assert!(information[4].surprisal < information[2].surprisal);
+        assert!(information.iter().all(|i| i.entropy >= 0.0));
+    }
+
+    #[test]
+    fn test_segment_lifecycle() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.segmenters = vec![Segmentation::FixedLength(2)];
+        let mut dimension = Dimension::new(0, &config);
+        let superior: Vec<Option<Spectrum>> = [0.0, 10.0, 20.0, 30.0, 40.0].iter()
+            .enumerate()
+            .map(|(i, value)| dimension.perceive(Spectrum::frame(vector(*value), i)).unwrap())
+            .collect();
+        let found: Vec<bool> = superior.iter().map(Option::is_some).collect();
+        assert_eq!(found, vec![false, false, true, false, true]);
+        assert_eq!(dimension.segments(), &[
+            Segment { start: 0, symbols: 2, length: 2, frames: Span::new(0, 2) },
+            Segment { start: 2, symbols: 2, length: 2, frames: Span::new(2, 4) },
+        ]);
+        let last = superior[4].as_ref().unwrap();
+        assert_eq!((last.span, last.frames), (Span::new(2, 4), Span::new(2, 4)));
+        // Every symbol is remembered and the boundary symbol begins the new segment
+        assert_eq!(dimension.episodic.sequence.len(), 5);
+        let ongoing: Vec<Label> = dimension.episodic.head.ongoing.iter().map(|s| s.label).collect();
+        assert_eq!(ongoing, vec![dimension.episodic.sequence[4].label]);
+        assert_eq!(dimension.episodic.head.previous.label, dimension.episodic.sequence[4].label);
+    }
+
+    #[test]
+    fn test_observe() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.segmenters = vec![Segmentation::FixedLength(2)];
+        let mut dimension = Dimension::new(0, &config);
+        for (i, value) in [0.0, 10.0, 0.0, 10.0].iter().enumerate() {
+            dimension.perceive(Spectrum::frame(vector(*value), i)).unwrap();
+        }
+        let (first, second) = (dimension.episodic.sequence[0].label, dimension.episodic.sequence[1].label);
+        let concepts = dimension.semantic.space.len();
+        let counts = dimension.unigram.total();
+
+        // Observation continues the stream where perception left off
+        let mut cursor = dimension.cursor();
+        let near = dimension.observe(&mut cursor, Spectrum::frame(vector(0.0), 4)).unwrap();
+        assert_eq!((near.category, near.novel), (first, false));
+        assert_eq!(near.prediction[0].0, first);
+        // The observed symbol finishes the segment of the last two perceived
+        let segment = near.segment.unwrap();
+        assert_eq!((segment.start, segment.symbols, segment.frames), (2, 2, Span::new(2, 4)));
+        assert_eq!(near.superior.unwrap().span, Span::new(2, 4));
+        // A point outside every category would begin a new one, so is
+        // categorized as the nearest
+        let far = dimension.observe(&mut cursor, Spectrum::frame(vector(90.0), 5)).unwrap();
+        assert_eq!((far.category, far.novel), (second, true));
+        assert!(far.segment.is_none() && far.superior.is_none());
+
+        // Nothing is learned from the observations
+        assert_eq!(dimension.semantic.space.len(), concepts);
+        assert_eq!(dimension.unigram.total(), counts);
+        assert_eq!(dimension.episodic.sequence.len(), 4);
+        assert_eq!(dimension.segments().len(), 1);
+    }
+
+    #[test]
+    fn test_freeze() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.segmenters = vec![Segmentation::FixedLength(2)];
+        let mut dimension = Dimension::new(0, &config);
+        for (i, value) in [0.0, 10.0, 0.0, 10.0].iter().enumerate() {
+            dimension.perceive(Spectrum::frame(vector(*value), i)).unwrap();
+        }
+        let mut cursor = dimension.cursor();
+        let observed: Vec<bool> = [0.0, 10.0, 0.0].iter().enumerate()
+            .map(|(i, value)| dimension.observe(&mut cursor, Spectrum::frame(vector(*value), 4 + i)).unwrap())
+            .map(|observation| observation.superior.is_some())
+            .collect();
+
+        // Frozen perception observes the same and remembers where it left off
+        dimension.freeze();
+        let perceived: Vec<bool> = [0.0, 10.0, 0.0].iter().enumerate()
+            .map(|(i, value)| dimension.perceive(Spectrum::frame(vector(*value), 4 + i)).unwrap())
+            .map(|superior| superior.is_some())
+            .collect();
+        assert_eq!(perceived, observed);
+        assert_eq!(dimension.last(), dimension.episodic.sequence[0].label);
+        assert_eq!(dimension.predict()[0].0, dimension.episodic.sequence[1].label);
+        assert_eq!(dimension.episodic.sequence.len(), 4);
+
+        // Frozen perception reports what it observes but learns nothing
+        let mut events = Vec::new();
+        let mut observer = |_: usize, _: u16, event: &Event| events.push(match event {
+            Event::Perceived { .. } => "perceived",
+            Event::Categorized { .. } => "categorized",
+            Event::Segmented { .. } => "segmented",
+            _ => "learned",
+        });
+        for (i, value) in [10.0, 0.0].iter().enumerate() {
+            dimension.perceive_with(Spectrum::frame(vector(*value), 7 + i), 7 + i, &mut observer).unwrap();
+        }
+        assert_eq!(events, vec!["perceived", "categorized", "perceived", "categorized", "segmented"]);
+    }
+
+    #[test]
+    fn test_reduction() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.segmenters = vec![Segmentation::FixedLength(2)];
+        config.reductions = vec![Reduction::Truncate(1)];
+        let mut dimension = Dimension::new(0, &config);
+        let superior: Vec<Spectrum> = [0.0, 10.0, 20.0].iter().enumerate()
+            .filter_map(|(i, value)| dimension.perceive(Spectrum::frame(vector(*value), i)).unwrap())
+            .collect();
+        // Only the constant coefficient of each of the 4 components is kept,
+        // which expands to the mean of the trajectory at every sample
+        assert_eq!(superior[0].point.len(), 4);
+        let trajectory = dimension.invert(&superior[0]).unwrap().unwrap();
+        assert_eq!(trajectory.dim(), (4, 4));
+        let mean = (&vector(0.0) + &vector(10.0)) / c64::new(2.0, 0.0);
+        assert!(trajectory.genrows().into_iter().all(|row| (&row - &mean).norm() < 1e-9));
+    }
+
+    #[test]
+    fn test_learned_pca() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.segmenters = vec![Segmentation::FixedLength(2)];
+        config.transformations = vec![Transformation::LearnedPca { components: 1, trajectories: 2 }];
+        let mut dimension = Dimension::new(0, &config);
+        let superior: Vec<Option<Spectrum>> = [0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0].iter()
+            .enumerate()
+            .map(|(i, value)| dimension.perceive(Spectrum::frame(vector(*value), i)).unwrap())
+            .collect();
+        // The first two segments only fit the axes, and later ones are
+        // projected onto the one axis at each of the 4 samples
+        let found: Vec<bool> = superior.iter().map(Option::is_some).collect();
+        assert_eq!(found, vec![false, false, false, false, false, false, true]);
+        assert!(matches!(dimension.transformation, Transformation::Pca(_)));
+        assert!(dimension.samples.is_empty());
+        assert_eq!(dimension.counters().segments, 1);
+        let last = superior[6].as_ref().unwrap();
+        assert_eq!(last.point.len(), 4);
+        assert_eq!(dimension.invert(last).unwrap().unwrap().dim(), (4, 4));
+    }
+
+    #[test]
+    fn test_segment_empty() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.segmenters = vec![Segmentation::FixedLength(0)];
+        let mut dimension = Dimension::new(0, &config);
+        // The first symbol cannot end a segment, every later one does
+        assert!(dimension.perceive(Spectrum::point(vector(0.0))).unwrap().is_none());
+        for value in &[10.0, 20.0, 30.0] {
+            assert!(dimension.perceive(Spectrum::point(vector(*value))).unwrap().is_some());
+        }
+        assert!(dimension.segments().iter().all(|segment| segment.symbols == 1));
+        assert_eq!(dimension.segments().len(), 3);
+    }
+
+    #[test]
+    fn test_segment_history() {
+        let mut episodic = EpisodicMemory::new(3);
+        for label in 1..=6 {
+            if label % 2 == 1 && label > 1 {
+                episodic.chop();
+            }
+            episodic.update(Symbol::new(label, 1));
+        }
+        // Only the segments with symbols still in the history are kept
+        assert_eq!(episodic.segments, vec![
+            Segment { start: 2, symbols: 2, length: 2, frames: Span::default() },
+        ]);
+        assert_eq!(episodic.head.ongoing.len(), 2);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut episodic = EpisodicMemory::new(3);
+        for label in 1..=5 {
+            episodic.update(Symbol::new(label, 2));
+        }
+        let labels: Vec<Label> = episodic.sequence.iter().map(|s| s.label).collect();
+        assert_eq!(labels, vec![3, 4, 5]);
+        assert_eq!(episodic.forgotten.symbols, 2);
+        assert_eq!(episodic.forgotten.length, 4);
+    }
+
+    #[test]
+    fn test_verify() {
+        let config = Config::default().unwrap();
+        let mut dimension = Dimension::new(0, &config);
+        dimension.perceive(Spectrum::point(vector(0.0))).unwrap();
+        assert!(dimension.verify().is_ok());
+        let wide = Spectrum::point((0..5).map(|_| c64::new(0.0, 0.0)).collect());
+        assert!(matches!(dimension.perceive(wide), Err(PredictError::Shape(_))));
+        dimension.semantic.space.insert(8, Concept::new(7, vector(1.0), 1.0, Structure::Diagonal));
+        assert!(matches!(dimension.verify(), Err(PredictError::Corrupted(_))));
+    }
+
+    #[test]
+    fn test_untransformable() {
+        // A projection fitted to samples of 2 components cannot abstract
+        // trajectories of 4, which fails before anything is perceived
+        let mut config = Config::default().unwrap();
+        let samples = Signal::from_shape_fn((4, 2), |(i, j)| c64::new((i * j) as f64, 0.0));
+        config.transformations = vec![Transformation::Pca(Pca::fit(&[samples], 1).unwrap())];
+        let mut dimension = Dimension::new(0, &config);
+        assert!(matches!(dimension.perceive(Spectrum::point(vector(0.0))), Err(PredictError::Shape(_))));
+        assert_eq!(dimension.counters().perceived, 0);
+        assert_eq!(dimension.unigram.total(), 0);
+        assert!(dimension.semantic.space.is_empty());
+        assert_eq!(dimension.episodic.seen(), 0);
+    }
+
+    #[test]
+    fn test_periods() {
+        let mut config = Config::default().unwrap();
+        config.consolidation_period = 4;
+        config.merge_threshold = 0.0;
+        config.split_threshold = f64::INFINITY;
+        config.forgetting_period = 3;
+        config.prune_below = 0;
+        let mut dimension = Dimension::new(0, &config);
+        let concepts: Vec<usize> = (0..12)
+            .map(|i| {
+                dimension.perceive(Spectrum::point(vector(100.0 * 10f64.powi(i)))).unwrap();
+                dimension.semantic.space.len()
+            })
+            .collect();
+        // Every spectrum founds a concept, and all concepts merge into one
+        // before every fourth, however often the counts decay
+        assert_eq!(concepts, vec![1, 2, 3, 4, 2, 3, 4, 5, 2, 3, 4, 5]);
+        assert!(dimension.unigram.total() < 12);
+    }
+
+    #[test]
+    fn test_forget() {
+        let config = Config::default().unwrap();
+        let mut dimension = Dimension::new(0, &config);
+        dimension.consolidation_period = 0;
+        for value in &[100.0, 50.0, 25.0, 0.0, 0.0, 0.0] {
+            dimension.perceive(Spectrum::point(vector(*value))).unwrap();
+        }
+        assert_eq!(dimension.semantic.space.len(), 4);
+        // Only the third symbol and the previous one are in use
+        let third = dimension.episodic.sequence[2].clone();
+        dimension.episodic.head.ongoing = vec![third];
+        dimension.decay = 0.5;
+        dimension.prune_below = 1;
+        dimension.forget();
+        // The rare early concepts go, the rare active one stays
+        assert_eq!(dimension.semantic.space.len(), 2);
+        assert!(dimension.unigram.total() < 12);
+    }
 }
@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong when loading a source, perceiving it, or
+/// saving and restoring the memory that results
+#[derive(Debug)]
+pub enum PredictError {
+    /// A file could not be read or written
+    Io(io::Error),
+    /// A source or reference file is not in the format it claims to be
+    Decoding(String),
+    /// The configuration asks for something that cannot be done
+    Configuration(String),
+    /// A vector, signal, or set of coefficients does not have the shape
+    /// required of it, e.g. a spectrum longer than the concepts of its level
+    Shape(String),
+    /// Restored memory is inconsistent, e.g. a concept filed under a label
+    /// other than its own
+    Corrupted(String),
+}
+
+impl fmt::Display for PredictError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PredictError::Io(error) => write!(f, "{}", error),
+            PredictError::Decoding(message) => write!(f, "decoding failed: {}", message),
+            PredictError::Configuration(message) => write!(f, "invalid configuration: {}", message),
+            PredictError::Shape(message) => write!(f, "shape mismatch: {}", message),
+            PredictError::Corrupted(message) => write!(f, "corrupted memory: {}", message),
+        }
+    }
+}
+
+impl Error for PredictError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PredictError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PredictError {
+    fn from(error: io::Error) -> PredictError {
+        PredictError::Io(error)
+    }
+}
+
+impl From<hound::Error> for PredictError {
+    fn from(error: hound::Error) -> PredictError {
+        match error {
+            hound::Error::IoError(error) => PredictError::Io(error),
+            error => PredictError::Decoding(error.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for PredictError {
+    fn from(error: serde_json::Error) -> PredictError {
+        if error.is_io() {
+            PredictError::Io(error.into())
+        } else {
+            PredictError::Decoding(error.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from() {
+        let missing = io::Error::new(io::ErrorKind::NotFound, "missing");
+        assert!(matches!(PredictError::from(missing), PredictError::Io(_)));
+        let wav = PredictError::from(hound::Error::FormatError("no RIFF tag found"));
+        assert!(matches!(wav, PredictError::Decoding(_)));
+        assert_eq!(wav.to_string(), "decoding failed: Ill-formed WAVE file: no RIFF tag found");
+        let json = serde_json::from_str::<Vec<usize>>("[1,").unwrap_err();
+        assert!(matches!(PredictError::from(json), PredictError::Decoding(_)));
+    }
+}
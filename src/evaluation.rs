@@ -0,0 +1,637 @@
+use crate::annotation::Timing;
+use crate::concept_symbol::Label;
+use crate::dimension::{Cursor, Dimension, Observation};
+use crate::error::PredictError;
+use crate::spectrum::Spectrum;
+use ndarray::Array1;
+use ndarray_linalg::types::c64;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Annotated unit of the reference, e.g. a phone or a word
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unit {
+    /// Time in seconds at which the unit begins
+    pub start: f64,
+    /// Time in seconds at which the unit ends
+    pub end: f64,
+    /// Transcription of the unit
+    pub label: String,
+}
+
+/// Format of a file of reference annotations
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reference {
+    /// TIMIT `.PHN` or `.WRD` file of `start end label` lines in samples
+    Timit,
+    /// Praat TextGrid, of which the first interval tier is read
+    TextGrid,
+    /// Audacity label track of `start end label` lines in seconds
+    Labels,
+}
+
+impl Reference {
+    /// Returns the format of the reference file, judged by its extension
+    ///
+    /// # Arguments
+    /// * `path` - path of the reference file
+    ///
+    pub fn of(path: &str) -> Reference {
+        let extension = Path::new(path).extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        match extension.as_deref() {
+            Some("phn") | Some("wrd") => Reference::Timit,
+            Some("textgrid") => Reference::TextGrid,
+            _ => Reference::Labels,
+        }
+    }
+}
+
+/// Precision, recall, and related scores of found boundaries
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Score {
+    /// Number of found boundaries within the tolerance of a reference boundary,
+    /// each reference boundary matching at most one found boundary
+    pub hits: usize,
+    /// Number of found boundaries
+    pub found: usize,
+    /// Number of reference boundaries
+    pub reference: usize,
+    /// Fraction of found boundaries that are hits
+    pub precision: f64,
+    /// Fraction of reference boundaries that are hit
+    pub recall: f64,
+    /// Harmonic mean of precision and recall
+    pub f1: f64,
+    /// R-value of Räsänen et al. (2009), which penalizes over-segmentation
+    pub r_value: f64,
+}
+
+/// Agreement of a clustering, e.g. the concepts of a dimension, with
+/// reference classes, e.g. the phones of a transcription
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Agreement {
+    /// Number of items clustered
+    pub count: usize,
+    /// Fraction of items in the majority class of their cluster
+    pub purity: f64,
+    /// Mutual information of clusters and classes, normalized by the geometric
+    /// mean of their entropies
+    pub nmi: f64,
+    /// Degree to which each cluster holds items of a single class
+    pub homogeneity: f64,
+    /// Degree to which the items of each class are in a single cluster
+    pub completeness: f64,
+    /// Harmonic mean of homogeneity and completeness
+    pub v_measure: f64,
+    /// Rand index adjusted for chance, 0 for random and 1 for equal partitions
+    pub ari: f64,
+}
+
+/// Quality of the predictions of the next label by a dimension
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Prediction {
+    /// Number of labels predicted
+    pub count: usize,
+    /// Mean surprisal in bits of the perceived labels under the predictions
+    pub cross_entropy: f64,
+    /// Number of equally likely labels with the same cross-entropy
+    pub perplexity: f64,
+    /// Fraction of labels that were predicted as the most probable
+    pub accuracy: f64,
+    /// Number of most probable labels counted as a correct prediction
+    pub k: usize,
+    /// Fraction of labels that were among the k most probable
+    pub top_k: f64,
+}
+
+/// Returns the units of the reference file
+///
+/// # Arguments
+/// * `path` - path of the reference file
+/// * `reference` - format of the reference file
+/// * `sample_rate` - number of samples per second, for TIMIT files
+///
+pub fn load(path: &str, reference: Reference, sample_rate: u32) -> Result<Vec<Unit>, PredictError> {
+    let text = fs::read_to_string(path)?;
+    match reference {
+        Reference::Timit => parse_lines(&text, sample_rate as f64),
+        Reference::Labels => parse_lines(&text, 1.0),
+        Reference::TextGrid => parse_textgrid(&text),
+    }
+}
+
+/// Returns the units of `start end label` lines, dividing times by the scale.
+/// Lines beginning with a backslash, i.e. Audacity's frequency ranges, are skipped.
+fn parse_lines(text: &str, scale: f64) -> Result<Vec<Unit>, PredictError> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('\\'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut time = || -> Result<f64, PredictError> {
+                let field = fields.next()
+                    .ok_or_else(|| PredictError::Decoding(format!("missing time in {:?}", line)))?;
+                Ok(number(field)? / scale)
+            };
+            let (start, end) = (time()?, time()?);
+            let label = fields.collect::<Vec<&str>>().join(" ");
+            Ok(Unit { start, end, label })
+        })
+        .collect()
+}
+
+/// Returns the labeled intervals of the first interval tier of a TextGrid
+fn parse_textgrid(text: &str) -> Result<Vec<Unit>, PredictError> {
+    let value = |line: &str| line.split_once('=')
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .ok_or_else(|| PredictError::Decoding(format!("missing value in {:?}", line)));
+    let mut units = Vec::new();
+    let mut tiers = 0;
+    let mut interval: Option<(f64, f64)> = None;
+    let mut start = 0.0;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("class =") {
+            tiers += 1;
+            if tiers > 1 {
+                break;
+            }
+        } else if line.starts_with("intervals [") {
+            interval = Some((0.0, 0.0));
+        } else if let Some((_, end)) = interval.as_mut() {
+            if line.starts_with("xmin =") {
+                start = number(&value(line)?)?;
+            } else if line.starts_with("xmax =") {
+                *end = number(&value(line)?)?;
+            } else if line.starts_with("text =") {
+                let label = value(line)?;
+                if !label.is_empty() {
+                    units.push(Unit { start, end: *end, label });
+                }
+                interval = None;
+            }
+        }
+    }
+    Ok(units)
+}
+
+/// Returns the number written in the field of a reference file
+fn number(field: &str) -> Result<f64, PredictError> {
+    field.parse().map_err(|_| PredictError::Decoding(format!("{:?} is not a time", field)))
+}
+
+/// Returns the times of the boundaries between and around the units, in order
+///
+/// # Arguments
+/// * `units` - annotated units
+///
+pub fn boundaries(units: &[Unit]) -> Vec<f64> {
+    let mut times: Vec<f64> = units.iter()
+        .flat_map(|unit| vec![unit.start, unit.end])
+        .collect();
+    dedup(&mut times);
+    times
+}
+
+/// Returns the times of the boundaries of the finished segments of the
+/// dimension, in order
+///
+/// # Arguments
+/// * `dimension` - dimension whose segments to find the boundaries of
+/// * `timing` - conversion from frames to seconds
+///
+pub fn segment_boundaries(dimension: &Dimension, timing: &Timing) -> Vec<f64> {
+    let mut times: Vec<f64> = dimension.segments().iter()
+        .flat_map(|segment| vec![segment.frames.start, segment.frames.end])
+        .map(|frame| timing.seconds(frame))
+        .collect();
+    dedup(&mut times);
+    times
+}
+
+/// Sorts the times and removes repeats
+fn dedup(times: &mut Vec<f64>) {
+    times.sort_by(|a, b| a.total_cmp(b));
+    times.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+}
+
+/// Returns the scores of the found boundaries against the reference boundaries.
+/// A found boundary is a hit if it lies within the tolerance of a reference
+/// boundary not already hit.
+///
+/// # Arguments
+/// * `found` - times of the found boundaries, in order
+/// * `reference` - times of the reference boundaries, in order
+/// * `tolerance` - largest distance in seconds of a hit from its reference
+///
+pub fn score(found: &[f64], reference: &[f64], tolerance: f64) -> Score {
+    let mut hits = 0;
+    let mut next = 0;
+    for &time in found.iter() {
+        while next < reference.len() && reference[next] < time - tolerance {
+            next += 1;
+        }
+        if next < reference.len() && reference[next] <= time + tolerance {
+            hits += 1;
+            next += 1;
+        }
+    }
+    let ratio = |a: usize, b: usize| if b == 0 { 0.0 } else { a as f64 / b as f64 };
+    let precision = ratio(hits, found.len());
+    let recall = ratio(hits, reference.len());
+    let f1 = if precision + recall > 0.0 {
+        2.0 * precision * recall / (precision + recall)
+    } else {
+        0.0
+    };
+    let over_segmentation = ratio(found.len(), reference.len()) - 1.0;
+    let r1 = ((1.0 - recall).powi(2) + over_segmentation.powi(2)).sqrt();
+    let r2 = (-over_segmentation + recall - 1.0) / 2f64.sqrt();
+    Score {
+        hits,
+        found: found.len(),
+        reference: reference.len(),
+        precision,
+        recall,
+        f1,
+        r_value: 1.0 - (r1.abs() + r2.abs()) / 2.0,
+    }
+}
+
+/// Returns the score of the segment boundaries of each dimension against
+/// the boundaries of the reference units
+///
+/// # Arguments
+/// * `dimensions` - dimensions of the memory, from the lowest level up
+/// * `units` - annotated units of the reference
+/// * `timing` - conversion from frames to seconds
+/// * `tolerance` - largest distance in seconds of a hit from its reference
+///
+pub fn evaluate(dimensions: &[Dimension], units: &[Unit], timing: &Timing, tolerance: f64) -> Vec<(u16, Score)> {
+    let reference = boundaries(units);
+    dimensions.iter()
+        .map(|dimension| {
+            let found = segment_boundaries(dimension, timing);
+            (dimension.level(), score(&found, &reference, tolerance))
+        })
+        .collect()
+}
+
+/// Returns the agreement of clusters and classes, from the cluster and class
+/// of each item. Without any items, every score is zero.
+///
+/// # Arguments
+/// * `pairs` - cluster and class of each item
+///
+pub fn agreement<A: Eq + Hash, B: Eq + Hash>(pairs: &[(A, B)]) -> Agreement {
+    if pairs.is_empty() {
+        return Agreement {
+            count: 0, purity: 0.0, nmi: 0.0, homogeneity: 0.0, completeness: 0.0, v_measure: 0.0, ari: 0.0,
+        };
+    }
+    let mut table: HashMap<(&A, &B), usize> = HashMap::new();
+    let mut clusters: HashMap<&A, usize> = HashMap::new();
+    let mut classes: HashMap<&B, usize> = HashMap::new();
+    for (cluster, class) in pairs.iter() {
+        *table.entry((cluster, class)).or_default() += 1;
+        *clusters.entry(cluster).or_default() += 1;
+        *classes.entry(class).or_default() += 1;
+    }
+    let n = pairs.len() as f64;
+    let cluster_entropy = entropy(clusters.values(), n);
+    let class_entropy = entropy(classes.values(), n);
+    let information: f64 = table.iter()
+        .map(|((cluster, class), &count)| {
+            let joint = count as f64 / n;
+            let marginals = clusters[cluster] as f64 * classes[class] as f64 / (n * n);
+            joint * (joint / marginals).ln()
+        })
+        .sum();
+
+    // Perfect by convention when there is nothing to explain
+    let ratio = |a: f64, b: f64| if b > 0.0 { a / b } else { 1.0 };
+    let homogeneity = ratio(information, class_entropy).min(1.0);
+    let completeness = ratio(information, cluster_entropy).min(1.0);
+    let v_measure = if homogeneity + completeness > 0.0 {
+        2.0 * homogeneity * completeness / (homogeneity + completeness)
+    } else {
+        0.0
+    };
+    let nmi = match (cluster_entropy > 0.0, class_entropy > 0.0) {
+        (true, true) => information / (cluster_entropy * class_entropy).sqrt(),
+        (false, false) => 1.0,
+        _ => 0.0,
+    };
+
+    let mut majorities: HashMap<&A, usize> = HashMap::new();
+    for ((cluster, _), &count) in table.iter() {
+        let majority = majorities.entry(cluster).or_default();
+        *majority = (*majority).max(count);
+    }
+    let purity = ratio(majorities.values().sum::<usize>() as f64, n);
+
+    let pairs_of = |count: usize| (count * count.saturating_sub(1)) as f64 / 2.0;
+    let index: f64 = table.values().map(|&count| pairs_of(count)).sum();
+    let cluster_pairs: f64 = clusters.values().map(|&count| pairs_of(count)).sum();
+    let class_pairs: f64 = classes.values().map(|&count| pairs_of(count)).sum();
+    let expected = ratio(cluster_pairs * class_pairs, pairs_of(pairs.len()));
+    let maximum = (cluster_pairs + class_pairs) / 2.0;
+    let ari = if maximum > expected { (index - expected) / (maximum - expected) } else { 1.0 };
+
+    Agreement { count: pairs.len(), purity, nmi, homogeneity, completeness, v_measure, ari }
+}
+
+/// Returns the entropy in nats of the partition of n items with the given counts
+fn entropy<'a>(counts: impl Iterator<Item = &'a usize>, n: f64) -> f64 {
+    -counts
+        .map(|&count| count as f64 / n)
+        .map(|p| p * p.ln())
+        .sum::<f64>()
+}
+
+/// Returns the concept label of each remembered symbol of the dimension,
+/// paired with the label of the reference unit it overlaps the most.
+/// Symbols that overlap no unit are left out.
+///
+/// # Arguments
+/// * `dimension` - dimension of which to align the symbols
+/// * `units` - annotated units of the reference, in order
+/// * `timing` - conversion from frames to seconds
+///
+pub fn align(dimension: &Dimension, units: &[Unit], timing: &Timing) -> Vec<(Label, String)> {
+    dimension.symbols()
+        .filter_map(|(_, symbol)| {
+            let start = timing.seconds(symbol.frames.start);
+            let end = timing.seconds(symbol.frames.end);
+            units.iter()
+                .map(|unit| (unit, end.min(unit.end) - start.max(unit.start)))
+                .filter(|(_, overlap)| *overlap > 0.0)
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(unit, _)| (symbol.label, unit.label.clone()))
+        })
+        .collect()
+}
+
+/// Returns the agreement of the concepts of each dimension with the labels
+/// of the reference units
+///
+/// # Arguments
+/// * `dimensions` - dimensions of the memory, from the lowest level up
+/// * `units` - annotated units of the reference, in order
+/// * `timing` - conversion from frames to seconds
+///
+pub fn cluster(dimensions: &[Dimension], units: &[Unit], timing: &Timing) -> Vec<(u16, Agreement)> {
+    dimensions.iter()
+        .map(|dimension| (dimension.level(), agreement(&align(dimension, units, timing))))
+        .collect()
+}
+
+/// Returns a table of the agreement of the concepts of each level
+///
+/// # Arguments
+/// * `agreements` - agreement of each level
+///
+pub fn cluster_report(agreements: &[(u16, Agreement)]) -> String {
+    let mut table = String::new();
+    writeln!(table, "{:>5} {:>6} {:>6} {:>6} {:>11} {:>12} {:>9} {:>6}",
+             "level", "items", "purity", "nmi", "homogeneity", "completeness", "v-measure", "ari").unwrap();
+    for (level, agreement) in agreements.iter() {
+        writeln!(table, "{:>5} {:>6} {:>6.3} {:>6.3} {:>11.3} {:>12.3} {:>9.3} {:>6.3}",
+                 level, agreement.count, agreement.purity, agreement.nmi, agreement.homogeneity,
+                 agreement.completeness, agreement.v_measure, agreement.ari).unwrap();
+    }
+    table
+}
+
+/// Observes the held-out signal with the dimensions without changing them,
+/// returning the quality of the predictions of each dimension of the label
+/// it perceives next. Fails if the held-out frames are unlike those the
+/// dimensions were trained on.
+///
+/// # Arguments
+/// * `dimensions` - trained dimensions of the memory, from the lowest level up
+/// * `signal` - held-out frames of the source
+/// * `k` - number of most probable labels counted as a correct prediction
+///
+pub fn predict(dimensions: &[Dimension], signal: Vec<Array1<c64>>, k: usize) -> Result<Vec<(u16, Prediction)>, PredictError> {
+    let mut tallies = vec![(0, 0.0, 0, 0); dimensions.len()];
+    let mut cursors: Vec<Cursor> = dimensions.iter().map(Dimension::cursor).collect();
+    for (i, point) in signal.into_iter().enumerate() {
+        let mut spectrum = Spectrum::frame(point, i);
+        let levels = dimensions.iter().zip(cursors.iter_mut()).zip(tallies.iter_mut());
+        for ((dimension, cursor), tally) in levels {
+            let Observation { category: label, prediction, superior, .. } = dimension.observe(cursor, spectrum)?;
+            let rank = prediction.iter().position(|(predicted, _)| *predicted == label);
+            let probability = match rank {
+                Some(rank) => prediction[rank].1,
+                None => 1.0 - prediction.iter().map(|(_, p)| p).sum::<f64>(),
+            };
+            tally.0 += 1;
+            tally.1 -= probability.max(f64::MIN_POSITIVE).log2();
+            tally.2 += (rank == Some(0)) as usize;
+            tally.3 += rank.is_some_and(|rank| rank < k) as usize;
+            match superior {
+                Some(result) => spectrum = result,
+                None => break,
+            }
+        }
+    }
+    Ok(dimensions.iter().zip(tallies)
+        .map(|(dimension, (count, bits, top, within))| {
+            let ratio = |a: f64| if count > 0 { a / count as f64 } else { 0.0 };
+            let cross_entropy = ratio(bits);
+            (dimension.level(), Prediction {
+                count,
+                cross_entropy,
+                perplexity: cross_entropy.exp2(),
+                accuracy: ratio(top as f64),
+                k,
+                top_k: ratio(within as f64),
+            })
+        })
+        .collect())
+}
+
+/// Returns a table of the quality of the predictions of each level
+///
+/// # Arguments
+/// * `predictions` - quality of the predictions of each level
+///
+pub fn prediction_report(predictions: &[(u16, Prediction)]) -> String {
+    let mut table = String::new();
+    let k = predictions.first().map_or(1, |(_, prediction)| prediction.k);
+    writeln!(table, "{:>5} {:>6} {:>13} {:>10} {:>8} {:>8}",
+             "level", "labels", "cross-entropy", "perplexity", "top-1", format!("top-{}", k)).unwrap();
+    for (level, prediction) in predictions.iter() {
+        writeln!(table, "{:>5} {:>6} {:>13.3} {:>10.3} {:>8.3} {:>8.3}",
+                 level, prediction.count, prediction.cross_entropy, prediction.perplexity,
+                 prediction.accuracy, prediction.top_k).unwrap();
+    }
+    table
+}
+
+/// Returns a table of the scores of each level
+///
+/// # Arguments
+/// * `scores` - score of each level
+///
+pub fn report(scores: &[(u16, Score)]) -> String {
+    let mut table = String::new();
+    writeln!(table, "{:>5} {:>6} {:>6} {:>6} {:>9} {:>6} {:>6} {:>7}",
+             "level", "found", "ref", "hits", "precision", "recall", "f1", "r-value").unwrap();
+    for (level, score) in scores.iter() {
+        writeln!(table, "{:>5} {:>6} {:>6} {:>6} {:>9.3} {:>6.3} {:>6.3} {:>7.3}",
+                 level, score.found, score.reference, score.hits,
+                 score.precision, score.recall, score.f1, score.r_value).unwrap();
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotation::{textgrid, Interval, Tier};
+    use crate::config::Config;
+    use crate::perception::process;
+    use ndarray::Array1;
+    use ndarray_linalg::types::c64;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_timit() -> Result<(), PredictError> {
+        let text = "0 3050 h#\n3050 4559 sh\n4559 5723 ix\n";
+        let units = parse_lines(text, 16000.0)?;
+        assert_eq!(units.len(), 3);
+        assert_eq!(units[1].label, "sh");
+        assert!(close(units[1].start, 3050.0 / 16000.0));
+        assert_eq!(boundaries(&units).len(), 4);
+        assert_eq!(Reference::of("SA1.PHN"), Reference::Timit);
+        assert_eq!(Reference::of("SA1.wrd"), Reference::Timit);
+        assert_eq!(Reference::of("SA1.TextGrid"), Reference::TextGrid);
+        assert_eq!(Reference::of("SA1.txt"), Reference::Labels);
+        Ok(())
+    }
+
+    #[test]
+    fn test_textgrid() -> Result<(), PredictError> {
+        let tier = |level, intervals: &[(f64, f64, usize)]| Tier {
+            level,
+            intervals: intervals.iter()
+                .map(|&(start, end, label)| Interval { start, end, label })
+                .collect(),
+        };
+        let grid = textgrid(&[tier(0, &[(0.25, 0.5, 3), (0.5, 1.0, 4)]), tier(1, &[(0.0, 1.0, 5)])]);
+        let units = parse_textgrid(&grid)?;
+        // The unlabeled padding is skipped and only the first tier is read
+        assert_eq!(units, vec![
+            Unit { start: 0.25, end: 0.5, label: "3".to_string() },
+            Unit { start: 0.5, end: 1.0, label: "4".to_string() },
+        ]);
+        Ok(())
+    }
+
+    fn pairs(clusters: &[usize], classes: &[&str]) -> Vec<(usize, String)> {
+        clusters.iter().zip(classes.iter())
+            .map(|(cluster, class)| (*cluster, class.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_agreement() {
+        // Equal partitions under different names agree perfectly
+        let equal = agreement(&pairs(&[7, 7, 3, 3, 5], &["a", "a", "b", "b", "c"]));
+        for value in &[equal.purity, equal.nmi, equal.v_measure, equal.ari] {
+            assert!(close(*value, 1.0));
+        }
+
+        // A single cluster is complete but not homogeneous
+        let single = agreement(&pairs(&[1, 1, 1, 1], &["a", "a", "b", "b"]));
+        assert!(close(single.purity, 0.5));
+        assert!(close(single.homogeneity, 0.0) && close(single.completeness, 1.0));
+        assert!(close(single.ari, 0.0) && close(single.nmi, 0.0));
+
+        // Splitting a class is homogeneous but not complete
+        let split = agreement(&pairs(&[0, 0, 1, 2], &["a", "a", "b", "b"]));
+        assert!(close(split.homogeneity, 1.0));
+        assert!(close(split.v_measure, 0.8));
+        assert!((split.ari - 0.5714285714).abs() < 1e-9);
+
+        let empty: Vec<(usize, String)> = Vec::new();
+        assert!(close(agreement(&empty).purity, 0.0));
+
+        let mixed = agreement(&pairs(&[0, 0, 1, 1, 2, 2], &["a", "a", "a", "b", "b", "b"]));
+        assert!((mixed.ari - 0.2424242424).abs() < 1e-9);
+        assert!(close(mixed.purity, 5.0 / 6.0));
+    }
+
+    #[test]
+    fn test_align() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.max_depth = 1;
+        let signal = [0.0, 0.0, 100.0, 100.0].iter()
+            .map(|&value| Array1::from(vec![c64::new(value, 0.0); 4]))
+            .collect();
+        let dimensions = process(&config, signal).unwrap();
+        let timing = Timing { sample_rate: 4, hop_size: 1 };
+        let units = vec![
+            Unit { start: 0.0, end: 0.7, label: "a".to_string() },
+            Unit { start: 0.7, end: 1.0, label: "b".to_string() },
+        ];
+        let aligned = align(&dimensions[0], &units, &timing);
+        let classes: Vec<&str> = aligned.iter().map(|(_, class)| class.as_str()).collect();
+        assert_eq!(classes, vec!["a", "a", "a", "b"]);
+        assert_eq!(cluster(&dimensions, &units, &timing)[0].1.count, 4);
+    }
+
+    #[test]
+    fn test_predict() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.max_depth = 1;
+        let frames = |values: &[f64]| -> Vec<Array1<c64>> {
+            values.iter().map(|&value| Array1::from(vec![c64::new(value, 0.0); 4])).collect()
+        };
+        let dimensions = process(&config, frames(&[0.0, 100.0, 0.0, 100.0, 0.0, 100.0])).unwrap();
+        let concepts = dimensions[0].symbols().count();
+
+        // The alternation is learned, so is predicted on held-out data, in
+        // which a point near a learned concept is categorized as that concept
+        let predictions = predict(&dimensions, frames(&[0.0, 100.0, 0.0, 100.0, 1.0]), 1).unwrap();
+        let (level, prediction) = predictions[0];
+        assert_eq!((level, prediction.count), (0, 5));
+        assert!(close(prediction.accuracy, 1.0) && close(prediction.top_k, 1.0));
+        assert!(prediction.cross_entropy < 1.0);
+        assert!(close(prediction.perplexity, prediction.cross_entropy.exp2()));
+        // Nothing was learned from the held-out data
+        assert_eq!(dimensions[0].symbols().count(), concepts);
+        assert!(prediction_report(&predictions).contains("top-1"));
+    }
+
+    #[test]
+    fn test_score() {
+        let reference = [0.0, 0.1, 0.2, 0.3];
+        let perfect = score(&reference, &reference, 0.02);
+        assert_eq!(perfect.hits, 4);
+        assert!(close(perfect.f1, 1.0) && close(perfect.r_value, 1.0));
+
+        // Two found boundaries near the same reference boundary hit it once
+        let found = [0.0, 0.11, 0.115, 0.25];
+        let partial = score(&found, &reference, 0.02);
+        assert_eq!(partial.hits, 2);
+        assert!(close(partial.precision, 0.5) && close(partial.recall, 0.5));
+
+        // Segmenting everywhere has perfect recall but a poor R-value
+        let everywhere: Vec<f64> = (0..=30).map(|i| i as f64 * 0.01).collect();
+        let over = score(&everywhere, &reference, 0.005);
+        assert!(close(over.recall, 1.0));
+        assert!(over.r_value < 0.0);
+        assert!(report(&[(0, over)]).lines().count() == 2);
+    }
+}
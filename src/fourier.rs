@@ -3,6 +3,9 @@ use ndarray::{Array, Axis, Dimension};
 use std::f64::consts::PI;
 use ndarray::{Array1, ArrayView1, s, azip};
 use ndarray_linalg::types::c64;
+use crate::error::PredictError;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 /// Convert from float vector to complex vector
 pub fn to_complex64(a: Array1<f64>) -> Array1<Complex64> {
@@ -10,17 +13,20 @@ pub fn to_complex64(a: Array1<f64>) -> Array1<Complex64> {
 //    vs.iter().map(|v| Complex64::new(*v, 0f64)).collect()
 }
 
-/// 1D Fast Fourier Transform
+/// 1D Fast Fourier Transform, falling back to the naive transform for
+/// lengths that are not a power of two
 pub fn fft(a: ArrayView1<c64>) -> Array1<c64> {
     let n = a.len();
-    if n == 1 {
+    if n <= 1 {
         a.into_owned()
+    } else if n % 2 != 0 {
+        dft(a)
     } else {
         let evens = fft(a.slice(s![..;2]));
         let odds = fft(a.slice(s![1..;2]));
         let mut comb = Array1::default(n);
         azip!((index k, &even in &evens, &odd in &odds) {
-            let change = odd * (SPEED * k as f64 / n as f64);
+            let change = odd * (SPEED * k as f64 / n as f64).exp();
             comb[k] = even + change;
             comb[k + n/2 as usize] = even - change;
         });
@@ -28,6 +34,33 @@ pub fn fft(a: ArrayView1<c64>) -> Array1<c64> {
     }
 }
 
+/// Short-time Fourier transform, i.e. the spectra of the frames of the
+/// given size that start every hop, which are found in parallel with the
+/// `parallel` feature. Fails unless the frames have a positive size and hop.
+///
+/// # Arguments
+/// * `a` - time-domain signal
+/// * `size` - number of samples in each frame
+/// * `hop` - number of samples between the starts of consecutive frames
+///
+pub fn stft(a: ArrayView1<c64>, size: usize, hop: usize) -> Result<Vec<Array1<c64>>, PredictError> {
+    if size == 0 || hop == 0 {
+        return Err(PredictError::Configuration("frames must have a positive size and hop".to_string()));
+    }
+    let count = if a.len() < size { 0 } else { (a.len() - size) / hop + 1 };
+    let frame = |i: usize| fft(a.slice(s![i * hop..i * hop + size]));
+    #[cfg(feature = "parallel")]
+    return Ok((0..count).into_par_iter().map(frame).collect());
+    #[cfg(not(feature = "parallel"))]
+    Ok((0..count).map(frame).collect())
+}
+
+/// 1D Inverse Fast Fourier Transform
+pub fn ifft(a: ArrayView1<c64>) -> Array1<c64> {
+    let n = a.len().max(1) as f64;
+    fft(a.mapv(|v| v.conj()).view()).mapv(|v| v.conj() / n)
+}
+
 //        let evens = (*vs).iter().enumerate()
 //            .filter(|&(i, _)| i % 2 == 0)
 //            .map(|(_, &v)| v)
@@ -53,20 +86,19 @@ const SPEED: Complex64 = Complex64 {
 };
 
 /// Naive 1D Discrete Fourier Transform
-#[allow(dead_code)]
-fn dft(vs: &Vec<Complex64>) -> Vec<Complex64> {
-    let mut fs: Vec<Complex64> = Vec::new();
+fn dft(vs: ArrayView1<c64>) -> Array1<c64> {
     let period = vs.len() as f64;
-    for k in 0..vs.len() {
-        let k = k as f64;
-        let mut f = Complex64::new(0f64, 0f64);
-        for (n, v) in vs.iter().enumerate() {
-            let n = n as f64;
-            f += v * (SPEED * k * n / period).exp();
-        }
-        fs.push(f);
-    }
-    fs
+    (0..vs.len())
+        .map(|k| {
+            let k = k as f64;
+            let mut f = Complex64::new(0f64, 0f64);
+            for (n, v) in vs.iter().enumerate() {
+                let n = n as f64;
+                f += v * (SPEED * k * n / period).exp();
+            }
+            f
+        })
+        .collect()
 }
 
 /// Naive MD Discrete Fourier Transform
@@ -94,4 +126,37 @@ fn mddft<D: Dimension>(vs: &Array<Complex64, D>) -> Array<Complex64, D> {
         }
     }
     fs
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: &Array1<c64>, b: &Array1<c64>) -> bool {
+        a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| (x - y).norm() < 1e-9)
+    }
+
+    #[test]
+    fn test_stft() {
+        let signal: Array1<c64> = (0..10).map(|i| c64::new(i as f64, 0.0)).collect();
+        let frames = stft(signal.view(), 4, 3).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert!(close(&frames[2], &fft(signal.slice(s![6..10]))));
+        assert!(stft(signal.view(), 16, 4).unwrap().is_empty());
+        assert!(matches!(stft(signal.view(), 4, 0), Err(PredictError::Configuration(_))));
+    }
+
+    #[test]
+    fn test_fft() {
+        for n in &[1, 2, 6, 8] {
+            let signal: Array1<c64> = (0..*n).map(|i| c64::new(i as f64, (i * i) as f64)).collect();
+            assert!(close(&fft(signal.view()), &dft(signal.view())));
+            assert!(close(&ifft(fft(signal.view()).view()), &signal));
+        }
+        // A single cosine cycle has energy only at the first and last bins
+        let cosine: Array1<c64> = (0..8).map(|i| c64::new((PI * i as f64 / 4.0).cos(), 0.0)).collect();
+        let spectrum = fft(cosine.view());
+        let expected: Array1<c64> = (0..8).map(|k| c64::new(if k == 1 || k == 7 { 4.0 } else { 0.0 }, 0.0)).collect();
+        assert!(close(&spectrum, &expected));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::concept_symbol::Label;
+use crate::dimension::Dimension;
+use crate::error::PredictError;
+use crate::spectrum::Span;
+use serde::{Serialize, Deserialize};
+use std::fs::File;
+use std::io::BufWriter;
+
+/// Symbol of some dimension together with the subordinate symbols it subtends
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Node {
+    /// Level of the dimension of the symbol
+    pub level: u16,
+    /// Position of the symbol among all symbols seen by its dimension
+    pub position: usize,
+    /// Label of the symbol
+    pub label: Label,
+    /// Positions of the subordinate symbols subtended by the symbol
+    pub span: Span,
+    /// Positions of the frames of the source subtended by the symbol
+    pub frames: Span,
+    /// Offsets of the samples of the source subtended by the symbol
+    pub samples: Span,
+    /// Subordinate symbols that are still remembered, in order
+    pub children: Vec<Node>,
+}
+
+/// Returns the remembered symbols of the dimensions as a forest, in which
+/// each symbol is the parent of the subordinate symbols it subtends. Symbols
+/// without a remembered parent, e.g. those of unfinished segments, are roots.
+/// The roots are ordered by the first frame they subtend.
+///
+/// # Arguments
+/// * `dimensions` - dimensions of the memory, from the lowest level up
+/// * `hop_size` - number of samples between the starts of consecutive frames
+///
+pub fn build(dimensions: &[Dimension], hop_size: usize) -> Vec<Node> {
+    let mut roots = Vec::new();
+    let mut subordinates: Vec<Node> = Vec::new();
+    for dimension in dimensions.iter() {
+        let mut nodes = Vec::new();
+        let mut orphans = subordinates.into_iter().peekable();
+        for (position, symbol) in dimension.symbols() {
+            let mut children = Vec::new();
+            while let Some(child) = orphans.peek() {
+                if child.position >= symbol.span.end {
+                    break;
+                }
+                let child = orphans.next().unwrap();
+                if child.position < symbol.span.start {
+                    roots.push(child);
+                } else {
+                    children.push(child);
+                }
+            }
+            nodes.push(Node {
+                level: dimension.level(),
+                position,
+                label: symbol.label,
+                span: symbol.span,
+                frames: symbol.frames,
+                samples: symbol.frames.scale(hop_size),
+                children,
+            });
+        }
+        roots.extend(orphans);
+        subordinates = nodes;
+    }
+    roots.extend(subordinates);
+    roots.sort_by_key(|node| node.frames.start);
+    roots
+}
+
+/// Writes the forest of symbols as JSON to the given path
+///
+/// # Arguments
+/// * `roots` - roots of the forest, as built from the dimensions
+/// * `path` - path of the file to write to
+///
+pub fn export(roots: &[Node], path: &str) -> Result<(), PredictError> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, roots)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::perception::process;
+    use crate::segmentation::Segmentation;
+    use ndarray::Array1;
+    use ndarray_linalg::types::c64;
+
+    fn signal(frames: usize) -> Vec<Array1<c64>> {
+        (0..frames)
+            .map(|i| Array1::from(vec![c64::new(10.0 * i as f64, 0.0); 4]))
+            .collect()
+    }
+
+    #[test]
+    fn test_build() {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.max_depth = 2;
+        config.segmenters = vec![Segmentation::FixedLength(2); 2];
+        let dimensions = process(&config, signal(8)).unwrap();
+        let roots = build(&dimensions, 16);
+
+        // Three symbols of two frames each at level 1, then the unfinished
+        // segment of level 0
+        let levels: Vec<u16> = roots.iter().map(|node| node.level).collect();
+        assert_eq!(levels, vec![1, 1, 1, 0, 0]);
+        let second = &roots[1];
+        assert_eq!(second.span, Span::new(2, 4));
+        assert_eq!(second.frames, Span::new(2, 4));
+        assert_eq!(second.samples, Span::new(32, 64));
+        let children: Vec<usize> = second.children.iter().map(|node| node.position).collect();
+        assert_eq!(children, vec![2, 3]);
+        assert_eq!(second.children[1].frames, Span::new(3, 4));
+        assert_eq!(roots[4].frames, Span::new(7, 8));
+    }
+
+    #[test]
+    fn test_export() -> Result<(), PredictError> {
+        let mut config = Config::default().unwrap();
+        config.resolution = 4;
+        config.max_depth = 2;
+        config.segmenters = vec![Segmentation::FixedLength(2); 2];
+        let roots = build(&process(&config, signal(6))?, 16);
+        let path = std::env::temp_dir().join(format!("predict_hierarchy_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        export(&roots, path)?;
+        let restored: Vec<Node> = serde_json::from_reader(File::open(path)?)?;
+        assert_eq!(restored, roots);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -1,22 +1,33 @@
 #![allow(dead_code)]
 
 pub mod abstraction;
+pub mod annotation;
 pub mod categorization;
 pub mod concept_symbol;
 pub mod config;
+pub mod covariance;
 pub mod deserialization;
 pub mod dimension;
+pub mod error;
+pub mod evaluation;
 pub mod fourier;
+pub mod hierarchy;
 pub mod loader;
+pub mod logging;
 pub mod markov_model;
+pub mod metric;
+pub mod observer;
 pub mod perception;
+pub mod reduction;
 pub mod segmentation;
 pub mod serialization;
 pub mod spectrum;
+pub mod transformation;
 pub mod visualization;
 
-use std::error::Error;
 use crate::dimension::Dimension;
+use crate::error::PredictError;
+use log::info;
 use ndarray::{s, Array1};
 use ndarray_linalg::types::c64;
 
@@ -25,19 +36,17 @@ use ndarray_linalg::types::c64;
 /// # Arguments
 /// * `config` - specifies all parameters with which to run the system
 ///
-pub fn run(config: config::Config) -> Result<(), Box<dyn Error>> {
+pub fn run(config: config::Config) -> Result<(), PredictError> {
 
-    // Load time-domain signal from wav file
-    let time_signal = loader::load_wav(&config.load_from)?;
-    let complex_signal = fourier::to_complex64(time_signal);
-//    let size = (complex_signal.len() as f64).log2().trunc().exp2() as usize;
-//    let frequency_signal = fourier::fft(complex_signal.slice(s![..size]));
-    let stft: Vec<Array1<c64>> = complex_signal.exact_chunks(16).into_iter()
-        .map(|chunk| fourier::fft(chunk))
-        .collect();
+    // Perceive the source
+    let (dimensions, timing) = learn(&config)?;
 
-    // Perceive frequency-domain signal
-    let dimensions = perception::process(&config, stft);
+    // Annotate the source with the symbols of each level
+    let tiers = annotation::tiers(&dimensions, &timing);
+    for format in config.annotations.iter() {
+        annotation::export(&tiers, *format, &config.save_at)?;
+        info!("wrote {:?} annotations to {}", format, config.save_at);
+    }
 
     // Save memory
 //    let serialized = serde_json::to_string(&dimensions).unwrap();
@@ -46,12 +55,91 @@ pub fn run(config: config::Config) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Run the system and return a per-level report of how well its segment
+/// boundaries match those of the reference annotations of the source
+///
+/// # Arguments
+/// * `config` - specifies all parameters with which to run the system
+/// * `reference` - path of the reference annotations (TIMIT, TextGrid, or labels)
+/// * `tolerance` - largest distance in seconds of a found boundary from its reference
+///
+pub fn evaluate(config: config::Config, reference: &str, tolerance: f64) -> Result<String, PredictError> {
+    let (dimensions, timing) = learn(&config)?;
+    let format = evaluation::Reference::of(reference);
+    let units = evaluation::load(reference, format, timing.sample_rate)?;
+    let scores = evaluation::evaluate(&dimensions, &units, &timing, tolerance);
+    Ok(evaluation::report(&scores))
+}
+
+/// Run the system and return a per-level report of how well its concepts
+/// agree with the labels of the reference annotations of the source
+///
+/// # Arguments
+/// * `config` - specifies all parameters with which to run the system
+/// * `reference` - path of the reference annotations (TIMIT, TextGrid, or labels)
+///
+pub fn clusters(config: config::Config, reference: &str) -> Result<String, PredictError> {
+    let (dimensions, timing) = learn(&config)?;
+    let format = evaluation::Reference::of(reference);
+    let units = evaluation::load(reference, format, timing.sample_rate)?;
+    let agreements = evaluation::cluster(&dimensions, &units, &timing);
+    Ok(evaluation::cluster_report(&agreements))
+}
+
+/// Run the system on the configured source, then return a per-level report
+/// of how well it predicts the labels of a held-out source it only observes
+///
+/// # Arguments
+/// * `config` - specifies all parameters with which to run the system
+/// * `test` - path of the held-out source, a RIFF or NIST SPHERE WAV file
+/// * `k` - number of most probable labels counted as a correct prediction
+///
+pub fn heldout(config: config::Config, test: &str, k: usize) -> Result<String, PredictError> {
+    let (dimensions, _) = learn(&config)?;
+    let audio = loader::load_audio(&test.to_string())?;
+    let predictions = evaluation::predict(&dimensions, frames(&config, audio.samples)?, k)?;
+    Ok(evaluation::prediction_report(&predictions))
+}
+
+/// Loads the source of the configuration and perceives it, returning the
+/// resulting dimensions and the timing of their frames
+///
+/// # Arguments
+/// * `config` - specifies all parameters with which to run the system
+///
+fn learn(config: &config::Config) -> Result<(Vec<Dimension>, annotation::Timing), PredictError> {
+    config.validate()?;
+
+    // Load time-domain signal from wav file
+    let audio = loader::load_audio(&config.load_from)?;
+    info!("loaded {} samples at {} Hz from {}", audio.samples.len(), audio.sample_rate, config.load_from);
+    let timing = annotation::Timing { sample_rate: audio.sample_rate, hop_size: config.hop_size };
+    let stft = frames(config, audio.samples)?;
+
+    // Perceive frequency-domain signal
+    let dimensions = perception::process(config, stft)?;
+    Ok((dimensions, timing))
+}
+
+/// Returns the spectra of the frames of the time-domain signal
+///
+/// # Arguments
+/// * `config` - specifies the size of and hop between frames
+/// * `samples` - time-domain signal
+///
+fn frames(config: &config::Config, samples: Array1<f64>) -> Result<Vec<Array1<c64>>, PredictError> {
+    let complex_signal = fourier::to_complex64(samples);
+//    let size = (complex_signal.len() as f64).log2().trunc().exp2() as usize;
+//    let frequency_signal = fourier::fft(complex_signal.slice(s![..size]));
+    fourier::stft(complex_signal.view(), config.frame_size, config.hop_size)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test() -> Result<(), Box<dyn std::error::Error>> {
+    fn test() -> Result<(), PredictError> {
         run(config::Config::default()?)
     }
 }
@@ -1,19 +1,93 @@
+use crate::error::PredictError;
 use hound;
 use std::fs;
-use std::io;
+use std::io::Read;
 use ndarray::Array1;
 
-pub fn load_text(path: &String) -> Result<Vec<String>, io::Error>  {
+/// Magic line at the start of a NIST SPHERE file, e.g. a TIMIT utterance
+const SPHERE: &[u8] = b"NIST_1A\n";
+
+pub fn load_text(path: &String) -> Result<Vec<String>, PredictError> {
     let text = fs::read_to_string(path)?;
     Ok(text.split_whitespace().map(|s| s.to_string()).collect())
 }
 
-pub fn load_wav(path: &String) -> Result<Array1<f64>, hound::Error> {
-    Ok(hound::WavReader::open(path)?
-        .into_samples()
+pub fn load_wav(path: &String) -> Result<Array1<f64>, PredictError> {
+    Ok(load_audio(path)?.samples)
+}
+
+/// Samples of a recording together with the rate at which they were taken
+pub struct Audio {
+    /// Samples scaled to [-1, 1)
+    pub samples: Array1<f64>,
+    /// Number of samples per second
+    pub sample_rate: u32,
+}
+
+/// Returns the samples and sample rate of the given WAV file, which may be
+/// either RIFF or NIST SPHERE (as used by TIMIT)
+///
+/// # Arguments
+/// * `path` - path of the WAV file, which must hold 16-bit samples
+///
+pub fn load_audio(path: &String) -> Result<Audio, PredictError> {
+    let mut magic = [0u8; 8];
+    let is_sphere = fs::File::open(path)?.read_exact(&mut magic).is_ok() && magic == SPHERE;
+    if is_sphere {
+        return load_sphere(path);
+    }
+    let reader = hound::WavReader::open(path)?;
+    let sample_rate = reader.spec().sample_rate;
+    let samples = reader.into_samples()
         .filter_map(Result::ok)
         .map(|sample: i16| sample as f64 / 32768.0) // Assumes wav is i16
-        .collect())
+        .collect();
+    Ok(Audio { samples, sample_rate })
+}
+
+/// Returns the samples and sample rate of the given NIST SPHERE file, whose
+/// header is a list of `name -type value` lines ending with `end_head`
+///
+/// # Arguments
+/// * `path` - path of the SPHERE file, which must hold uncompressed
+///   16-bit samples
+///
+pub fn load_sphere(path: &String) -> Result<Audio, PredictError> {
+    let bytes = fs::read(path)?;
+    let size: usize = bytes.get(SPHERE.len()..16)
+        .and_then(|line| std::str::from_utf8(line).ok())
+        .and_then(|line| line.trim().parse().ok())
+        .ok_or_else(|| PredictError::Decoding("missing SPHERE header size".to_string()))?;
+    let header = bytes.get(..size)
+        .ok_or_else(|| PredictError::Decoding("truncated SPHERE header".to_string()))?;
+    let header = String::from_utf8_lossy(header);
+    let field = |name: &str| header.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+        .find(|fields| fields.len() == 3 && fields[0] == name)
+        .map(|fields| fields[2].to_string());
+
+    let sample_rate = field("sample_rate")
+        .and_then(|rate| rate.parse().ok())
+        .ok_or_else(|| PredictError::Decoding("missing SPHERE sample rate".to_string()))?;
+    if let Some(n) = field("sample_n_bytes").filter(|n| n != "2") {
+        return Err(PredictError::Decoding(format!("unsupported SPHERE sample size {}", n)));
+    }
+    if let Some(coding) = field("sample_coding").filter(|coding| coding != "pcm") {
+        return Err(PredictError::Decoding(format!("unsupported SPHERE sample coding {}", coding)));
+    }
+    let channels: usize = field("channel_count").and_then(|n| n.parse().ok()).unwrap_or(1);
+    let big_endian = field("sample_byte_format").is_some_and(|format| format == "10");
+
+    // Keep only the first channel of interleaved samples
+    let samples = bytes[size..].chunks_exact(2)
+        .step_by(channels.max(1))
+        .map(|pair| {
+            let pair = [pair[0], pair[1]];
+            if big_endian { i16::from_be_bytes(pair) } else { i16::from_le_bytes(pair) }
+        })
+        .map(|sample| sample as f64 / 32768.0)
+        .collect();
+    Ok(Audio { samples, sample_rate })
 }
 
 #[allow(dead_code)]
@@ -53,6 +127,24 @@ fn slides<T>(slice: &Vec<T>, window: usize, step: usize) -> Slides<T> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sphere() -> Result<(), PredictError> {
+        let audio = load_audio(&"SA1.WAV".to_string())?;
+        assert_eq!(audio.sample_rate, 16000);
+        assert_eq!(audio.samples.len(), 46797);
+        let max = audio.samples.iter().cloned().fold(f64::MIN, f64::max);
+        assert!((max - 2790.0 / 32768.0).abs() < 1e-12);
+        Ok(())
+    }
+
+    #[test]
+    fn test_riff() -> Result<(), PredictError> {
+        let audio = load_audio(&"export.wav".to_string())?;
+        assert_eq!(audio.sample_rate, 16000);
+        assert_eq!(audio.samples, load_wav(&"export.wav".to_string())?);
+        Ok(())
+    }
+
     #[test]
     fn test() {
         //        let slides_1 = slides(&samples, 8, 4);
@@ -0,0 +1,47 @@
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Writes each diagnostic to standard error, prefixed by its level and the
+/// module it came from
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{:<5} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger;
+
+/// Logs the diagnostics at most as detailed as the verbosity to standard
+/// error. If a logger is already installed, only the verbosity changes.
+///
+/// # Arguments
+/// * `verbosity` - most detailed level of the diagnostics to log
+///
+pub fn init(verbosity: LevelFilter) {
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(verbosity);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init() {
+        init(LevelFilter::Debug);
+        assert_eq!(log::max_level(), LevelFilter::Debug);
+        assert!(log::log_enabled!(log::Level::Debug));
+        assert!(!log::log_enabled!(log::Level::Trace));
+        init(LevelFilter::Warn);
+        assert_eq!(log::max_level(), LevelFilter::Warn);
+    }
+}
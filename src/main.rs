@@ -1,20 +1,96 @@
 #![allow(dead_code)]
 
+use log::LevelFilter;
 use predict::config::Config;
-use std::env;
+use predict::logging;
 use std::process;
+use structopt::StructOpt;
+
+/// Learns a hierarchy of concepts and segments from audio
+#[derive(StructOpt)]
+#[structopt(name = "predict")]
+struct Options {
+    /// Log more diagnostics: -v for debug, -vv for trace
+    #[structopt(short, long, parse(from_occurrences))]
+    verbose: u8,
+    /// Log only errors and hide the progress bar
+    #[structopt(short, long)]
+    quiet: bool,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Perceive the configured source (the default)
+    Run,
+    /// Perceive a source and score the segment boundaries of each level
+    /// against reference annotations
+    Evaluate {
+        /// Reference annotations: TIMIT .PHN/.WRD, Praat .TextGrid, or Audacity labels
+        reference: String,
+        /// Source to perceive, a RIFF or NIST SPHERE WAV file
+        #[structopt(long, default_value = "SA1.WAV")]
+        audio: String,
+        /// Largest distance in seconds of a found boundary from its reference
+        #[structopt(long, default_value = "0.02")]
+        tolerance: f64,
+    },
+    /// Perceive a source and score the agreement of the concepts of each level
+    /// with the labels of reference annotations
+    Clusters {
+        /// Reference annotations: TIMIT .PHN/.WRD, Praat .TextGrid, or Audacity labels
+        reference: String,
+        /// Source to perceive, a RIFF or NIST SPHERE WAV file
+        #[structopt(long, default_value = "SA1.WAV")]
+        audio: String,
+    },
+    /// Perceive a training source, then freeze the memory and score its
+    /// predictions of the next label at each level on a held-out source
+    Heldout {
+        /// Held-out source, a RIFF or NIST SPHERE WAV file
+        test: String,
+        /// Training source, a RIFF or NIST SPHERE WAV file
+        #[structopt(long, default_value = "export.wav")]
+        train: String,
+        /// Number of most probable labels counted as a correct prediction
+        #[structopt(long, default_value = "5")]
+        top_k: usize,
+    },
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-//    let args: Vec<String> = env::args().collect();
-//    let config: Config = Config::new(&args).unwrap_or_else(|err| {
-//        eprintln!("Problem parsing arguments: {}", err);
-//        process::exit(1);
-//    });
-    let config = Config::default()?;
-    let result = predict::run(config);
-//    if let Err(e) = result {
-//        eprintln!("Application error: {}", e);
-//        process::exit(1);
-//    }
+    let options = Options::from_args();
+    let mut config = Config::default()?;
+    config.progress = !options.quiet;
+    config.verbosity = match (options.quiet, options.verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => config.verbosity,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    logging::init(config.verbosity);
+    let result = match options.command.unwrap_or(Command::Run) {
+        Command::Run => predict::run(config),
+        Command::Evaluate { reference, audio, tolerance } => {
+            config.load_from = audio;
+            predict::evaluate(config, &reference, tolerance)
+                .map(|report| print!("{}", report))
+        }
+        Command::Clusters { reference, audio } => {
+            config.load_from = audio;
+            predict::clusters(config, &reference)
+                .map(|report| print!("{}", report))
+        }
+        Command::Heldout { test, train, top_k } => {
+            config.load_from = train;
+            predict::heldout(config, &test, top_k)
+                .map(|report| print!("{}", report))
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Application error: {}", e);
+        process::exit(1);
+    }
     Ok(())
 }
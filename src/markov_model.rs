@@ -1,8 +1,67 @@
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::ops::Index;
 use serde::{Serialize, Deserialize};
 
+/// Discount subtracted from every count by Kneser-Ney smoothing
+const DISCOUNT: f64 = 0.75;
+
+/// Method of estimating probabilities from counts. Every method reserves some
+/// probability for keys that have never been seen, so that the probability of
+/// a novel key, or of any key in a novel context, is well defined.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Smoothing {
+    /// Add one to the count of every seen key and of a single unseen key
+    Laplace,
+    /// Interpolate with the lower order in proportion to the number of
+    /// different keys seen in the context
+    #[default]
+    WittenBell,
+    /// Subtract a fixed discount from every count and interpolate with the
+    /// lower order, of which the counts are the number of different contexts
+    KneserNey,
+    /// Interpolate with the lower order using the escape probability 1 / (n + 1)
+    /// of PPM method A
+    Escape,
+}
+
+/// Returns the entropy in bits of a distribution
+///
+/// # Arguments
+/// * `probabilities` - probability of each outcome
+///
+fn entropy(probabilities: impl Iterator<Item = f64>) -> f64 {
+    -probabilities
+        .filter(|&p| p > 0.0)
+        .map(|p| p * p.log2())
+        .sum::<f64>()
+}
+
+impl Smoothing {
+    /// Returns the smoothed probability of a key in some context
+    ///
+    /// # Arguments
+    /// * `count` - number of times the key was seen in the context
+    /// * `total` - number of times any key was seen in the context
+    /// * `distinct` - number of different keys seen in the context
+    /// * `vocabulary` - number of different keys seen anywhere, plus one unseen
+    /// * `lower` - probability of the key under the lower order
+    ///
+    fn estimate(&self, count: usize, total: usize, distinct: usize, vocabulary: usize, lower: f64) -> f64 {
+        if total == 0 {
+            return lower;
+        }
+        let (c, n, t) = (count as f64, total as f64, distinct as f64);
+        match self {
+            Smoothing::Laplace => (c + 1.0) / (n + vocabulary as f64),
+            Smoothing::WittenBell => (c + t * lower) / (n + t),
+            Smoothing::KneserNey => ((c - DISCOUNT).max(0.0) + DISCOUNT * t * lower) / n,
+            Smoothing::Escape => (c + lower) / (n + 1.0),
+        }
+    }
+}
+
 /// Counts the number of times a given (length 1) key has been seen
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UnigramModel<K>
@@ -46,6 +105,137 @@ where
             None => 0,
         }
     }
+
+    /// Return the total number of keys seen.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Return the number of different keys seen.
+    pub fn distinct(&self) -> usize {
+        self.unigram.len()
+    }
+
+    /// Move the count of one key onto another, e.g. when categories merge.
+    ///
+    /// # Arguments
+    /// * `from` - key whose count is moved and which is then forgotten
+    /// * `into` - key that receives the count
+    ///
+    pub fn relabel(&mut self, from: &K, into: &K) {
+        if let Some(count) = self.unigram.remove(from) {
+            *self.unigram.entry(*into).or_insert(0) += count;
+        }
+    }
+
+    /// Move a share of the count of one key onto another, e.g. when a
+    /// category splits.
+    ///
+    /// # Arguments
+    /// * `from` - key whose count is shared
+    /// * `into` - key that receives the share
+    /// * `share` - fraction between 0 and 1 of the count to move, rounded
+    ///
+    pub fn split(&mut self, from: &K, into: &K, share: f64) {
+        let moved = (self.count(from) as f64 * share).round() as usize;
+        if moved == 0 {
+            return;
+        }
+        *self.unigram.get_mut(from).unwrap() -= moved;
+        self.unigram.retain(|_, count| *count > 0);
+        *self.unigram.entry(*into).or_insert(0) += moved;
+    }
+
+    /// Scale every count by the given factor, rounding down, and forget the
+    /// keys whose count drops to zero.
+    ///
+    /// # Arguments
+    /// * `factor` - factor between 0 and 1 by which to scale the counts
+    ///
+    pub fn decay(&mut self, factor: f64) {
+        for count in self.unigram.values_mut() {
+            *count = (*count as f64 * factor) as usize;
+        }
+        self.unigram.retain(|_, count| *count > 0);
+        self.total = self.unigram.values().sum();
+    }
+
+    /// Forget the given key and its count.
+    ///
+    /// # Arguments
+    /// * `key` - key to forget
+    ///
+    pub fn remove(&mut self, key: &K) {
+        if let Some(count) = self.unigram.remove(key) {
+            self.total -= count;
+        }
+    }
+
+    /// Return the keys seen, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.unigram.keys()
+    }
+
+    /// Return the smoothed probability of the given key, which may be unseen.
+    ///
+    /// # Arguments
+    /// * `key` - key of which to estimate the probability
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn probability(&self, key: &K, smoothing: Smoothing) -> f64 {
+        self.estimate(self.count(key), smoothing)
+    }
+
+    /// Return the smoothed probability that the next key has never been seen.
+    ///
+    /// # Arguments
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn novel(&self, smoothing: Smoothing) -> f64 {
+        self.estimate(0, smoothing)
+    }
+
+    /// Return the surprisal in bits of seeing the given key, i.e. -log2 p.
+    ///
+    /// # Arguments
+    /// * `key` - key that was seen, which may be unseen before
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn surprisal(&self, key: &K, smoothing: Smoothing) -> f64 {
+        -self.probability(key, smoothing).log2()
+    }
+
+    /// Return the entropy in bits of the distribution over the seen keys and
+    /// a single unseen key.
+    ///
+    /// # Arguments
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn entropy(&self, smoothing: Smoothing) -> f64 {
+        entropy(self.keys()
+            .map(|key| self.probability(key, smoothing))
+            .chain(std::iter::once(self.novel(smoothing))))
+    }
+
+    /// Smoothed probability of a key with the given count, against a uniform
+    /// distribution over the seen keys and a single unseen key
+    fn estimate(&self, count: usize, smoothing: Smoothing) -> f64 {
+        let vocabulary = self.distinct() + 1;
+        let uniform = 1.0 / vocabulary as f64;
+        smoothing.estimate(count, self.total, self.distinct(), vocabulary, uniform)
+    }
+
+    /// Add all counts of the given model into this model.
+    ///
+    /// # Arguments
+    /// * `other` - model of which to add the counts
+    ///
+    pub fn absorb(&mut self, other: UnigramModel<K>) {
+        self.total += other.total;
+        for (key, count) in other.unigram {
+            *self.unigram.entry(key).or_insert(0) += count;
+        }
+    }
 }
 
 impl<K> Index<K> for UnigramModel<K>
@@ -55,7 +245,7 @@ where
     type Output = usize;
 
     fn index(&self, index: K) -> &Self::Output {
-        &self.unigram.get(&index).unwrap()
+        self.unigram.get(&index).unwrap_or(&0)
     }
 }
 
@@ -108,6 +298,588 @@ where
             None => 0,
         }
     }
+
+    /// Return the count of the given pair
+    ///
+    /// # Arguments
+    /// * `first` - first key in the pair
+    /// * `second` - second key in the pair
+    ///
+    pub fn count_pair(&self, first: &K, second: &K) -> usize {
+        match self.bigram.get(first) {
+            Some(unigram) => unigram.count(second),
+            None => 0,
+        }
+    }
+
+    /// Move the counts of one key onto another in both positions of the pairs
+    ///
+    /// # Arguments
+    /// * `from` - key whose counts are moved and which is then forgotten
+    /// * `into` - key that receives the counts
+    ///
+    pub fn relabel(&mut self, from: &K, into: &K) {
+        if let Some(unigram) = self.bigram.remove(from) {
+            self.bigram.entry(*into).or_insert_with(UnigramModel::new).absorb(unigram);
+        }
+        for unigram in self.bigram.values_mut() {
+            unigram.relabel(from, into);
+        }
+    }
+
+    /// Scale every count by the given factor, rounding down, and forget the
+    /// pairs whose count drops to zero.
+    ///
+    /// # Arguments
+    /// * `factor` - factor between 0 and 1 by which to scale the counts
+    ///
+    pub fn decay(&mut self, factor: f64) {
+        for unigram in self.bigram.values_mut() {
+            unigram.decay(factor);
+        }
+        self.bigram.retain(|_, unigram| unigram.total > 0);
+        self.total = self.bigram.values().map(|unigram| unigram.total).sum();
+    }
+
+    /// Return the smoothed probability of the second key following the first,
+    /// either of which may be unseen.
+    ///
+    /// # Arguments
+    /// * `first` - first key in the pair
+    /// * `second` - second key in the pair
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn probability(&self, first: &K, second: &K, smoothing: Smoothing) -> f64 {
+        self.conditional(first, Some(second), &self.lower(smoothing), smoothing)
+    }
+
+    /// Return the smoothed probability that the key following the first key
+    /// has never been seen as the second key of a pair.
+    ///
+    /// # Arguments
+    /// * `first` - first key in the pair
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn novel(&self, first: &K, smoothing: Smoothing) -> f64 {
+        self.conditional(first, None, &self.lower(smoothing), smoothing)
+    }
+
+    /// Return the surprisal in bits of seeing the second key after the first.
+    ///
+    /// # Arguments
+    /// * `first` - first key in the pair
+    /// * `second` - second key in the pair
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn surprisal(&self, first: &K, second: &K, smoothing: Smoothing) -> f64 {
+        -self.probability(first, second, smoothing).log2()
+    }
+
+    /// Return the entropy in bits of the distribution of the key following
+    /// the first key, over the seen second keys and a single unseen key.
+    ///
+    /// # Arguments
+    /// * `first` - first key in the pair
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn entropy(&self, first: &K, smoothing: Smoothing) -> f64 {
+        let lower = self.lower(smoothing);
+        entropy(lower.keys()
+            .map(|second| self.conditional(first, Some(second), &lower, smoothing))
+            .chain(std::iter::once(self.conditional(first, None, &lower, smoothing))))
+    }
+
+    /// Smoothed probability of the second key (None if unseen) after the
+    /// first, given the lower-order model of the second keys
+    fn conditional(&self, first: &K, second: Option<&K>, lower: &UnigramModel<K>, smoothing: Smoothing) -> f64 {
+        match second {
+            Some(second) => self.estimate(first, self.count_pair(first, second), lower.probability(second, smoothing), lower, smoothing),
+            None => self.estimate(first, 0, lower.novel(smoothing), lower, smoothing),
+        }
+    }
+
+    /// Smoothed probability of a second key with the given count after the first
+    fn estimate(&self, first: &K, count: usize, lower: f64, model: &UnigramModel<K>, smoothing: Smoothing) -> f64 {
+        let (total, distinct) = match self.bigram.get(first) {
+            Some(unigram) => (unigram.total, unigram.distinct()),
+            None => (0, 0),
+        };
+        smoothing.estimate(count, total, distinct, model.distinct() + 1, lower)
+    }
+
+    /// Returns the lower-order model of the second keys: their counts, or for
+    /// Kneser-Ney the number of different first keys they follow.
+    fn lower(&self, smoothing: Smoothing) -> UnigramModel<K> {
+        let mut lower = UnigramModel::new();
+        for unigram in self.bigram.values() {
+            for (key, count) in unigram.unigram.iter() {
+                let count = if smoothing == Smoothing::KneserNey { 1 } else { *count };
+                *lower.unigram.entry(*key).or_insert(0) += count;
+                lower.total += count;
+            }
+        }
+        lower
+    }
+
+    /// Forget all pairs in which the given key appears.
+    ///
+    /// # Arguments
+    /// * `key` - key to forget
+    ///
+    pub fn remove(&mut self, key: &K) {
+        self.bigram.remove(key);
+        for unigram in self.bigram.values_mut() {
+            unigram.remove(key);
+        }
+        self.bigram.retain(|_, unigram| unigram.total > 0);
+        self.total = self.bigram.values().map(|unigram| unigram.total).sum();
+    }
+}
+
+/// Node in the trie of an n-gram model, for the sequence along its path
+#[derive(Serialize, Deserialize, Debug)]
+struct Node<K>
+where
+    K: Eq + Hash + Copy,
+{
+    /// Number of times the sequence ending at this node has been seen
+    count: usize,
+    /// Nodes of the sequences extended by one key
+    children: HashMap<K, Node<K>>,
+}
+
+impl<K> Node<K>
+where
+    K: Eq + Hash + Copy,
+{
+    /// Returns a node that has not been seen
+    fn new() -> Node<K> {
+        Node {
+            count: 0,
+            children: HashMap::new(),
+        }
+    }
+
+    /// Returns the node at the end of the path, if it exists
+    fn get(&self, path: &[K]) -> Option<&Node<K>> {
+        match path.split_first() {
+            Some((key, rest)) => self.children.get(key)?.get(rest),
+            None => Some(self),
+        }
+    }
+
+    /// Increments the node at the end of the path, creating it if necessary
+    fn increment(&mut self, path: &[K]) {
+        match path.split_first() {
+            Some((key, rest)) => self.children.entry(*key)
+                .or_insert_with(Node::new)
+                .increment(rest),
+            None => self.count += 1,
+        }
+    }
+
+    /// Adds the counts of the given node and its descendants into this node
+    fn absorb(&mut self, other: Node<K>) {
+        self.count += other.count;
+        for (key, child) in other.children {
+            self.children.entry(key).or_insert_with(Node::new).absorb(child);
+        }
+    }
+
+    /// Moves the subtrees of one key onto another at every depth
+    fn relabel(&mut self, from: &K, into: &K) {
+        if let Some(child) = self.children.remove(from) {
+            self.children.entry(*into).or_insert_with(Node::new).absorb(child);
+        }
+        for child in self.children.values_mut() {
+            child.relabel(from, into);
+        }
+    }
+
+    /// Moves a share of the subtrees of one key onto another at every depth
+    fn split(&mut self, from: &K, into: &K, share: f64) {
+        for child in self.children.values_mut() {
+            child.split(from, into, share);
+        }
+        if let Some(child) = self.children.get_mut(from) {
+            let part = child.part(share);
+            if child.count == 0 && child.children.is_empty() {
+                self.children.remove(from);
+            }
+            if part.count > 0 || !part.children.is_empty() {
+                self.children.entry(*into).or_insert_with(Node::new).absorb(part);
+            }
+        }
+    }
+
+    /// Takes a share of the counts of this node and its descendants, rounded,
+    /// forgetting the descendants that vanish. Returns the share taken.
+    fn part(&mut self, share: f64) -> Node<K> {
+        let count = (self.count as f64 * share).round() as usize;
+        self.count -= count;
+        let children = self.children.iter_mut()
+            .map(|(key, child)| (*key, child.part(share)))
+            .filter(|(_, part)| part.count > 0 || !part.children.is_empty())
+            .collect();
+        self.children.retain(|_, child| child.count > 0 || !child.children.is_empty());
+        Node { count, children }
+    }
+
+    /// Scales the counts of the descendants, forgetting those that vanish
+    fn decay(&mut self, factor: f64) {
+        for child in self.children.values_mut() {
+            child.count = (child.count as f64 * factor) as usize;
+            child.decay(factor);
+        }
+        self.children.retain(|_, child| child.count > 0 || !child.children.is_empty());
+    }
+
+    /// Forgets the subtrees of the given key at every depth
+    fn remove(&mut self, key: &K) {
+        self.children.remove(key);
+        for child in self.children.values_mut() {
+            child.remove(key);
+        }
+    }
+}
+
+/// Counts of the keys seen after some context
+struct Level<K> {
+    /// Number of times each key was seen after the context
+    counts: HashMap<K, usize>,
+    /// Sum of the counts
+    total: usize,
+}
+
+/// Counts the number of times sequences of up to `order` keys have been seen,
+/// such that the next key can be predicted from a variable-length context.
+/// The sequences are stored in a trie, so any context can be queried.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NGramModel<K>
+where
+    K: Eq + Hash + Copy,
+{
+    /// Maximum length of a sequence (i.e. context length + 1)
+    order: usize,
+    /// Root of the trie, of which the count is the total number of keys seen
+    root: Node<K>,
+    /// Most recent keys, used as the context of the next key
+    history: Vec<K>,
+    /// Entropy of the next key without any context, with the smoothing it
+    /// was estimated by, kept until the counts change
+    #[serde(skip)]
+    unconditional: Cell<Option<(Smoothing, f64)>>,
+}
+
+impl<K> NGramModel<K>
+where
+    K: Eq + Hash + Copy,
+{
+    /// Returns an empty n-gram model
+    ///
+    /// # Arguments
+    /// * `order` - maximum length of a counted sequence, at least 1
+    ///
+    pub fn new(order: usize) -> NGramModel<K> {
+        NGramModel {
+            order: order.max(1),
+            root: Node::new(),
+            history: Vec::new(),
+            unconditional: Cell::new(None),
+        }
+    }
+
+    /// Return the maximum length of a counted sequence
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Return the most recent keys, which form the context of the next key
+    pub fn context(&self) -> &[K] {
+        &self.history
+    }
+
+    /// Add the key following the most recent keys, then remember the key.
+    ///
+    /// # Arguments
+    /// * `key` - key that was seen
+    ///
+    pub fn update(&mut self, key: &K) {
+        let history = std::mem::take(&mut self.history);
+        self.increment(&history, key);
+        self.history = history;
+        self.advance(key);
+    }
+
+    /// Remember the key as the most recent without counting it.
+    ///
+    /// # Arguments
+    /// * `key` - key that was seen
+    ///
+    pub fn advance(&mut self, key: &K) {
+        self.history.push(*key);
+        let excess = self.history.len().saturating_sub(self.order - 1);
+        self.history.drain(..excess);
+    }
+
+    /// Add the key following the given context, i.e. count every suffix of
+    /// the context (up to `order - 1` keys long) followed by the key.
+    ///
+    /// # Arguments
+    /// * `context` - keys preceding the key, most recent last
+    /// * `key` - key that was seen
+    ///
+    pub fn increment(&mut self, context: &[K], key: &K) {
+        self.unconditional.set(None);
+        let context = self.truncate(context);
+        let mut sequence = context.to_vec();
+        sequence.push(*key);
+        self.root.count += 1;
+        for start in 0..sequence.len() {
+            self.root.increment(&sequence[start..]);
+        }
+    }
+
+    /// Return the number of times the given sequence has been seen.
+    /// The empty sequence has been seen once for every key.
+    ///
+    /// # Arguments
+    /// * `sequence` - keys in order, at most `order` long
+    ///
+    pub fn count(&self, sequence: &[K]) -> usize {
+        self.root.get(sequence).map_or(0, |node| node.count)
+    }
+
+    /// Return the number of times the given context has been followed by a key
+    ///
+    /// # Arguments
+    /// * `context` - keys in order, most recent last
+    ///
+    pub fn context_count(&self, context: &[K]) -> usize {
+        self.root.get(self.truncate(context))
+            .map_or(0, |node| node.children.values().map(|child| child.count).sum())
+    }
+
+    /// Return the keys seen after the given context, with their counts
+    ///
+    /// # Arguments
+    /// * `context` - keys in order, most recent last
+    ///
+    pub fn successors(&self, context: &[K]) -> Vec<(K, usize)> {
+        self.root.get(self.truncate(context))
+            .map_or(Vec::new(), |node| node.children.iter()
+                .filter(|(_, child)| child.count > 0)
+                .map(|(key, child)| (*key, child.count))
+                .collect())
+    }
+
+    /// Return the smoothed probability of the key following the given context.
+    /// Contexts that have not been seen back off to shorter contexts, down to
+    /// a uniform distribution over the seen keys and a single unseen key.
+    ///
+    /// # Arguments
+    /// * `context` - keys in order, most recent last
+    /// * `key` - key of which to estimate the probability, which may be unseen
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn probability(&self, context: &[K], key: &K, smoothing: Smoothing) -> f64 {
+        let levels = self.levels(self.seen(context), smoothing);
+        self.estimate(&levels, Some(key), smoothing, self.vocabulary())
+    }
+
+    /// Return the smoothed probability that the key following the given
+    /// context has never been seen.
+    ///
+    /// # Arguments
+    /// * `context` - keys in order, most recent last
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn novel(&self, context: &[K], smoothing: Smoothing) -> f64 {
+        let levels = self.levels(self.seen(context), smoothing);
+        self.estimate(&levels, None, smoothing, self.vocabulary())
+    }
+
+    /// Return the smoothed probability of each seen key following the given
+    /// context, in no particular order. The remaining probability is that of
+    /// a key never seen.
+    ///
+    /// # Arguments
+    /// * `context` - keys in order, most recent last
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn probabilities(&self, context: &[K], smoothing: Smoothing) -> Vec<(K, f64)> {
+        let levels = self.levels(self.seen(context), smoothing);
+        let vocabulary = self.vocabulary();
+        self.keys().into_iter()
+            .map(|key| (key, self.estimate(&levels, Some(&key), smoothing, vocabulary)))
+            .collect()
+    }
+
+    /// Return the surprisal in bits of seeing the key after the context.
+    ///
+    /// # Arguments
+    /// * `context` - keys in order, most recent last
+    /// * `key` - key that was seen, which may be unseen before
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn surprisal(&self, context: &[K], key: &K, smoothing: Smoothing) -> f64 {
+        -self.probability(context, key, smoothing).log2()
+    }
+
+    /// Return the conditional entropy in bits of the key following the
+    /// context, over the seen keys and a single unseen key. The entropy
+    /// without any context is kept until the counts change.
+    ///
+    /// # Arguments
+    /// * `context` - keys in order, most recent last
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn entropy(&self, context: &[K], smoothing: Smoothing) -> f64 {
+        let context = self.seen(context);
+        match self.unconditional.get() {
+            Some((estimated, entropy)) if context.is_empty() && estimated == smoothing => return entropy,
+            _ => (),
+        }
+        let levels = self.levels(context, smoothing);
+        let vocabulary = self.vocabulary();
+        let conditional = entropy(self.keys().iter()
+            .map(|key| self.estimate(&levels, Some(key), smoothing, vocabulary))
+            .chain(std::iter::once(self.estimate(&levels, None, smoothing, vocabulary))));
+        if context.is_empty() {
+            self.unconditional.set(Some((smoothing, conditional)));
+        }
+        conditional
+    }
+
+    /// Return the information in bits that the context gives about the next
+    /// key, i.e. the entropy without any context less that given the context.
+    ///
+    /// # Arguments
+    /// * `context` - keys in order, most recent last
+    /// * `smoothing` - method of estimation
+    ///
+    pub fn information_gain(&self, context: &[K], smoothing: Smoothing) -> f64 {
+        self.entropy(&[], smoothing) - self.entropy(context, smoothing)
+    }
+
+    /// Return the keys seen, in no particular order.
+    pub fn keys(&self) -> Vec<K> {
+        self.root.children.iter()
+            .filter(|(_, child)| child.count > 0)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Number of different keys seen, plus a single unseen key
+    fn vocabulary(&self) -> usize {
+        self.root.children.values().filter(|child| child.count > 0).count() + 1
+    }
+
+    /// Smoothed probability of the key (None if unseen) given the counts
+    /// after each suffix of a context, interpolated from the shortest suffix
+    /// up, starting from a uniform distribution over the vocabulary
+    fn estimate(&self, levels: &[Level<K>], key: Option<&K>, smoothing: Smoothing, vocabulary: usize) -> f64 {
+        levels.iter().rev().fold(1.0 / vocabulary as f64, |lower, level| {
+            let count = key.and_then(|key| level.counts.get(key)).copied().unwrap_or(0);
+            smoothing.estimate(count, level.total, level.counts.len(), vocabulary, lower)
+        })
+    }
+
+    /// Returns the counts of the keys after each suffix of the context,
+    /// longest first and down to the empty suffix. The counts of all but the
+    /// longest are continuation counts when smoothing by Kneser-Ney.
+    fn levels(&self, context: &[K], smoothing: Smoothing) -> Vec<Level<K>> {
+        (0..=context.len())
+            .map(|start| {
+                let suffix = &context[start..];
+                let counts = if smoothing == Smoothing::KneserNey && start > 0 {
+                    self.continuations(suffix)
+                } else {
+                    self.successors(suffix).into_iter().collect()
+                };
+                Level { total: counts.values().sum(), counts }
+            })
+            .collect()
+    }
+
+    /// Longest suffix of the context that has been seen followed by some key
+    fn seen<'a>(&self, context: &'a [K]) -> &'a [K] {
+        let mut context = self.truncate(context);
+        while !context.is_empty() && self.context_count(context) == 0 {
+            context = &context[1..];
+        }
+        context
+    }
+
+    /// Returns the keys seen after the context, each with the number of
+    /// different keys seen before the context followed by that key
+    fn continuations(&self, context: &[K]) -> HashMap<K, usize> {
+        let mut continuations = HashMap::new();
+        for first in self.root.children.values() {
+            if let Some(node) = first.get(context) {
+                for (key, _) in node.children.iter().filter(|(_, child)| child.count > 0) {
+                    *continuations.entry(*key).or_insert(0) += 1;
+                }
+            }
+        }
+        continuations
+    }
+
+    /// Move the counts of one key onto another wherever it appears
+    ///
+    /// # Arguments
+    /// * `from` - key whose counts are moved and which is then forgotten
+    /// * `into` - key that receives the counts
+    ///
+    pub fn relabel(&mut self, from: &K, into: &K) {
+        self.unconditional.set(None);
+        self.root.relabel(from, into);
+        for key in self.history.iter_mut().filter(|key| *key == from) {
+            *key = *into;
+        }
+    }
+
+    /// Move a share of the counts of one key onto another wherever it
+    /// appears, e.g. when a category splits. The most recent keys keep the
+    /// key that is shared.
+    ///
+    /// # Arguments
+    /// * `from` - key whose counts are shared
+    /// * `into` - key that receives the share
+    /// * `share` - fraction between 0 and 1 of the counts to move, rounded
+    ///
+    pub fn split(&mut self, from: &K, into: &K, share: f64) {
+        self.unconditional.set(None);
+        self.root.split(from, into, share);
+    }
+
+    /// Scale every count by the given factor, rounding down, and forget the
+    /// sequences whose count drops to zero.
+    ///
+    /// # Arguments
+    /// * `factor` - factor between 0 and 1 by which to scale the counts
+    ///
+    pub fn decay(&mut self, factor: f64) {
+        self.unconditional.set(None);
+        self.root.decay(factor);
+        self.root.count = self.root.children.values().map(|child| child.count).sum();
+    }
+
+    /// Forget all sequences in which the given key appears.
+    ///
+    /// # Arguments
+    /// * `key` - key to forget
+    ///
+    pub fn remove(&mut self, key: &K) {
+        self.unconditional.set(None);
+        self.root.remove(key);
+        self.root.count = self.root.children.values().map(|child| child.count).sum();
+    }
+
+    /// Returns the most recent `order - 1` keys of the context
+    fn truncate<'a>(&self, context: &'a [K]) -> &'a [K] {
+        &context[context.len().saturating_sub(self.order - 1)..]
+    }
 }
 
 #[cfg(test)]
@@ -115,5 +887,260 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test() {}
+    fn test_relabel() {
+        let mut unigram = UnigramModel::new();
+        let mut bigram = BigramModel::new();
+        let sequence = [1, 2, 1, 3, 2, 3];
+        for (first, second) in sequence.iter().zip(sequence.iter().skip(1)) {
+            unigram.increment(second);
+            bigram.increment(first, second);
+        }
+        unigram.relabel(&3, &1);
+        bigram.relabel(&3, &1);
+        assert_eq!(unigram.count(&1), 3);
+        assert_eq!(unigram.count(&3), 0);
+        assert_eq!(unigram.total(), 5);
+        assert_eq!(bigram.count(&1), 3);
+        assert_eq!(bigram.count_pair(&1, &1), 1);
+        assert_eq!(bigram.count_pair(&2, &1), 2);
+        assert_eq!(bigram.count_pair(&1, &2), 2);
+        assert_eq!(bigram.count(&3), 0);
+    }
+
+    #[test]
+    fn test_decay() {
+        let mut unigram = UnigramModel::new();
+        let mut bigram = BigramModel::new();
+        let sequence = [1, 1, 1, 1, 2, 1, 1, 3];
+        for (first, second) in sequence.iter().zip(sequence.iter().skip(1)) {
+            unigram.increment(second);
+            bigram.increment(first, second);
+        }
+        unigram.decay(0.5);
+        bigram.decay(0.5);
+        assert_eq!(unigram.count(&1), 2);
+        assert_eq!(unigram.count(&2), 0);
+        assert_eq!(unigram.total(), 2);
+        assert_eq!(unigram.distinct(), 1);
+        assert_eq!(bigram.count_pair(&1, &1), 2);
+        assert_eq!(bigram.count(&2), 0);
+        assert_eq!(bigram.count(&1), 2);
+
+        bigram.remove(&1);
+        unigram.remove(&1);
+        assert_eq!(bigram.count(&1), 0);
+        assert_eq!(unigram.total(), 0);
+    }
+
+    #[test]
+    fn test_ngram() {
+        let mut ngram = NGramModel::new(3);
+        for key in &[1, 2, 3, 1, 2, 1, 2, 3] {
+            ngram.update(key);
+        }
+        assert_eq!(ngram.count(&[]), 8);
+        assert_eq!(ngram.count(&[1]), 3);
+        assert_eq!(ngram.count(&[1, 2]), 3);
+        assert_eq!(ngram.count(&[1, 2, 3]), 2);
+        assert_eq!(ngram.count(&[2, 1, 2]), 1);
+        assert_eq!(ngram.count(&[3, 3]), 0);
+        assert_eq!(ngram.context(), &[2, 3]);
+        assert_eq!(ngram.context_count(&[1, 2]), 3);
+        assert_eq!(ngram.context_count(&[9, 1, 2]), 3);
+        let mut successors = ngram.successors(&[2]);
+        successors.sort();
+        assert_eq!(successors, vec![(1, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn test_ngram_advance() {
+        let mut ngram = NGramModel::new(3);
+        for key in &[1, 2, 3] {
+            ngram.update(key);
+        }
+        ngram.advance(&1);
+        ngram.advance(&2);
+        assert_eq!(ngram.context(), &[1, 2]);
+        assert_eq!(ngram.count(&[1, 2]), 1);
+        assert_eq!(ngram.count(&[2, 1]), 0);
+    }
+
+    #[test]
+    fn test_ngram_bigram() {
+        let sequence = [1, 2, 1, 3, 2, 3, 3];
+        let mut ngram = NGramModel::new(2);
+        let mut bigram = BigramModel::new();
+        ngram.update(&sequence[0]);
+        for (first, second) in sequence.iter().zip(sequence.iter().skip(1)) {
+            ngram.update(second);
+            bigram.increment(first, second);
+        }
+        for first in 1..4 {
+            assert_eq!(ngram.context_count(&[first]), bigram.count(&first));
+            for second in 1..4 {
+                assert_eq!(ngram.count(&[first, second]), bigram.count_pair(&first, &second));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ngram_forgetting() {
+        let mut ngram = NGramModel::new(2);
+        for key in &[1, 2, 1, 3, 1, 1] {
+            ngram.update(key);
+        }
+        ngram.relabel(&3, &2);
+        assert_eq!(ngram.count(&[1, 2]), 2);
+        assert_eq!(ngram.count(&[2]), 2);
+        assert_eq!(ngram.count(&[3]), 0);
+        ngram.decay(0.5);
+        assert_eq!(ngram.count(&[1]), 2);
+        assert_eq!(ngram.count(&[2]), 1);
+        assert_eq!(ngram.count(&[1, 2]), 1);
+        assert_eq!(ngram.count(&[]), 3);
+        ngram.remove(&2);
+        assert_eq!(ngram.count(&[]), 2);
+        assert_eq!(ngram.count(&[1, 2]), 0);
+    }
+
+    #[test]
+    fn test_split() {
+        let mut unigram = UnigramModel::new();
+        let mut ngram = NGramModel::new(2);
+        for key in &[1, 2, 1, 2, 1, 1] {
+            unigram.increment(key);
+            ngram.update(key);
+        }
+        unigram.split(&1, &3, 0.5);
+        ngram.split(&1, &3, 0.5);
+        assert_eq!((unigram.count(&1), unigram.count(&3), unigram.total()), (2, 2, 6));
+        assert_eq!((ngram.count(&[1]), ngram.count(&[3]), ngram.count(&[])), (2, 2, 6));
+        // The pairs are shared in both positions without changing their number
+        assert_eq!(ngram.count(&[1, 2]) + ngram.count(&[3, 2]), 2);
+        assert_eq!(ngram.count(&[2, 1]) + ngram.count(&[2, 3]), 2);
+        assert!(ngram.count(&[3, 2]) > 0 && ngram.count(&[2, 3]) > 0);
+        assert_eq!(ngram.context(), &[1]);
+    }
+
+    const SMOOTHINGS: [Smoothing; 4] = [
+        Smoothing::Laplace, Smoothing::WittenBell, Smoothing::KneserNey, Smoothing::Escape
+    ];
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_unigram_probability() {
+        let mut unigram = UnigramModel::new();
+        for key in &[1, 1, 1, 2] {
+            unigram.increment(key);
+        }
+        assert_eq!(unigram[7], 0);
+        for &smoothing in SMOOTHINGS.iter() {
+            let sum = unigram.probability(&1, smoothing)
+                + unigram.probability(&2, smoothing)
+                + unigram.novel(smoothing);
+            assert!(close(sum, 1.0));
+            assert!(close(unigram.probability(&7, smoothing), unigram.novel(smoothing)));
+            assert!(unigram.probability(&1, smoothing) > unigram.probability(&2, smoothing));
+            assert!(unigram.novel(smoothing) > 0.0);
+        }
+        assert!(close(unigram.probability(&1, Smoothing::Laplace), 4.0 / 7.0));
+        assert!(close(unigram.probability(&1, Smoothing::WittenBell), (3.0 + 2.0 / 3.0) / 6.0));
+        assert!(close(unigram.novel(Smoothing::Escape), 1.0 / 15.0));
+        let empty: UnigramModel<usize> = UnigramModel::new();
+        assert!(close(empty.novel(Smoothing::KneserNey), 1.0));
+    }
+
+    #[test]
+    fn test_bigram_probability() {
+        let sequence = [1, 2, 1, 3, 1, 2, 2, 3];
+        let mut bigram = BigramModel::new();
+        for (first, second) in sequence.iter().zip(sequence.iter().skip(1)) {
+            bigram.increment(first, second);
+        }
+        for &smoothing in SMOOTHINGS.iter() {
+            for first in &[1, 2, 3, 9] {
+                let sum: f64 = [1, 2, 3].iter()
+                    .map(|second| bigram.probability(first, second, smoothing))
+                    .sum::<f64>() + bigram.novel(first, smoothing);
+                assert!(close(sum, 1.0));
+            }
+            assert!(bigram.probability(&1, &2, smoothing) > bigram.probability(&1, &1, smoothing));
+        }
+    }
+
+    #[test]
+    fn test_ngram_probability() {
+        let mut ngram = NGramModel::new(3);
+        for key in &[1, 2, 3, 1, 2, 1, 2, 3, 3, 1] {
+            ngram.update(key);
+        }
+        let contexts: Vec<Vec<usize>> = vec![vec![], vec![1], vec![1, 2], vec![9, 9], vec![3, 9]];
+        for &smoothing in SMOOTHINGS.iter() {
+            for context in contexts.iter() {
+                let sum: f64 = [1, 2, 3].iter()
+                    .map(|key| ngram.probability(context, key, smoothing))
+                    .sum::<f64>() + ngram.novel(context, smoothing);
+                assert!(close(sum, 1.0));
+                for (key, probability) in ngram.probabilities(context, smoothing) {
+                    assert!(close(probability, ngram.probability(context, &key, smoothing)));
+                }
+            }
+            assert!(ngram.probability(&[1], &2, smoothing) > ngram.probability(&[1], &3, smoothing));
+            // An unseen context is predicted by the shorter contexts
+            assert!(close(ngram.probability(&[9, 9], &2, smoothing), ngram.probability(&[], &2, smoothing)));
+        }
+    }
+
+    #[test]
+    fn test_information() {
+        let smoothing = Smoothing::WittenBell;
+        let mut unigram = UnigramModel::new();
+        for key in &[1, 2, 3, 4] {
+            unigram.increment(key);
+        }
+        // Four equally likely keys and some chance of a novel one
+        assert!(unigram.entropy(smoothing) > 2.0);
+        assert!(close(unigram.surprisal(&1, smoothing), -unigram.probability(&1, smoothing).log2()));
+
+        let mut ngram = NGramModel::new(2);
+        let mut bigram = BigramModel::new();
+        let sequence = [1, 2, 1, 2, 1, 2, 1, 2, 1];
+        for (first, second) in sequence.iter().zip(sequence.iter().skip(1)) {
+            bigram.increment(first, second);
+        }
+        for key in sequence.iter() {
+            ngram.update(key);
+        }
+        // The previous key determines the next in an alternating sequence
+        assert!(ngram.entropy(&[1], smoothing) < ngram.entropy(&[], smoothing));
+        assert!(ngram.information_gain(&[1], smoothing) > 0.5);
+        assert!(close(ngram.information_gain(&[], smoothing), 0.0));
+        assert!(ngram.surprisal(&[1], &2, smoothing) < ngram.surprisal(&[1], &1, smoothing));
+        assert!(bigram.entropy(&1, smoothing) < 1.0);
+        assert!(bigram.surprisal(&2, &1, smoothing) < bigram.surprisal(&2, &2, smoothing));
+
+        // The entropy without context is kept only until the counts change
+        let alternating = ngram.entropy(&[], smoothing);
+        assert!(!close(ngram.entropy(&[], Smoothing::Laplace), alternating));
+        for _ in 0..9 {
+            ngram.update(&3);
+        }
+        assert!(ngram.entropy(&[], smoothing) > alternating);
+        assert!(close(ngram.entropy(&[9], smoothing), ngram.entropy(&[], smoothing)));
+    }
+
+    #[test]
+    fn test_ngram_serde() {
+        let mut ngram = NGramModel::new(3);
+        for key in &[1usize, 2, 3, 1, 2] {
+            ngram.update(key);
+        }
+        let json = serde_json::to_string(&ngram).unwrap();
+        let restored: NGramModel<usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.count(&[1, 2]), 2);
+        assert_eq!(restored.context(), ngram.context());
+    }
 }
@@ -0,0 +1,202 @@
+use crate::concept_symbol::Concept;
+use crate::covariance::quantile;
+use ndarray_linalg::norm::Norm;
+use ndarray_linalg::types::c64;
+use serde::{Serialize, Deserialize};
+
+/// Smallest power or variance considered when dividing or taking logarithms
+const FLOOR: f64 = 1e-12;
+
+/// Measures how far a concept lies from a category in the semantic space.
+/// Each metric also expresses the radius of the category in its own units,
+/// so that membership is decided by comparing the two.
+pub trait Metric {
+    /// Returns the distance from the category to the target concept
+    ///
+    /// # Arguments
+    /// * `category` - category to measure from
+    /// * `target` - concept to measure to
+    ///
+    fn distance(&self, category: &Concept, target: &Concept) -> f64;
+
+    /// Returns the radius of the category in the units of this metric
+    ///
+    /// # Arguments
+    /// * `category` - category of which to find the radius
+    ///
+    fn radius(&self, category: &Concept) -> f64;
+}
+
+/// Straight-line distance between centroids, against the cached radius
+pub struct Euclidean;
+
+impl Metric for Euclidean {
+    fn distance(&self, category: &Concept, target: &Concept) -> f64 {
+        (&category.location.centroid - &target.location.centroid).norm()
+    }
+
+    fn radius(&self, category: &Concept) -> f64 {
+        category.location.radius
+    }
+}
+
+/// Distance scaled by the posterior covariance of the category
+pub struct Mahalanobis;
+
+impl Metric for Mahalanobis {
+    fn distance(&self, category: &Concept, target: &Concept) -> f64 {
+        let deviation = &target.location.centroid - &category.location.centroid;
+        category.moments.posterior_variance.mahalanobis(&deviation).sqrt()
+    }
+
+    /// Quantile of the distance of the category's own mass
+    fn radius(&self, category: &Concept) -> f64 {
+        quantile(category.location.centroid.len()).sqrt()
+    }
+}
+
+/// One minus the cosine of the angle between centroids, taken as real vectors,
+/// so a centroid and its negation are 2 apart
+pub struct Cosine;
+
+impl Metric for Cosine {
+    fn distance(&self, category: &Concept, target: &Concept) -> f64 {
+        let a = &category.location.centroid;
+        let b = &target.location.centroid;
+        let norms = a.norm() * b.norm();
+        if norms < FLOOR {
+            return 1.0;
+        }
+        let inner: f64 = a.iter().zip(b.iter())
+            .map(|(x, y)| x.conj() * y)
+            .sum::<c64>()
+            .re;
+        (1.0 - inner / norms).max(0.0)
+    }
+
+    /// Half-angle of the cone of directions passing through the category
+    fn radius(&self, category: &Concept) -> f64 {
+        let norm = category.location.centroid.norm();
+        let ratio = category.location.radius / norm.max(FLOOR);
+        if ratio >= 1.0 {
+            1.0
+        } else {
+            1.0 - (1.0 - ratio.powi(2)).sqrt()
+        }
+    }
+}
+
+/// Root-mean-square difference of the power spectra in decibels
+pub struct LogSpectral;
+
+impl Metric for LogSpectral {
+    fn distance(&self, category: &Concept, target: &Concept) -> f64 {
+        let a = &category.location.centroid;
+        let b = &target.location.centroid;
+        if a.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = a.iter().zip(b.iter())
+            .map(|(x, y)| {
+                let ratio = x.norm_sqr().max(FLOOR) / y.norm_sqr().max(FLOOR);
+                (10.0 * ratio.log10()).powi(2)
+            })
+            .sum();
+        (sum / a.len() as f64).sqrt()
+    }
+
+    /// Deviation of the centroid scaled out to the edge of the category
+    fn radius(&self, category: &Concept) -> f64 {
+        let norm = category.location.centroid.norm();
+        20.0 * (1.0 + category.location.radius / norm.max(FLOOR)).log10()
+    }
+}
+
+/// Selects which metric decides category membership in a dimension
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Distance {
+    #[default]
+    Euclidean,
+    Mahalanobis,
+    Cosine,
+    LogSpectral,
+}
+
+impl Metric for Distance {
+    fn distance(&self, category: &Concept, target: &Concept) -> f64 {
+        match self {
+            Distance::Euclidean => Euclidean.distance(category, target),
+            Distance::Mahalanobis => Mahalanobis.distance(category, target),
+            Distance::Cosine => Cosine.distance(category, target),
+            Distance::LogSpectral => LogSpectral.distance(category, target),
+        }
+    }
+
+    fn radius(&self, category: &Concept) -> f64 {
+        match self {
+            Distance::Euclidean => Euclidean.radius(category),
+            Distance::Mahalanobis => Mahalanobis.radius(category),
+            Distance::Cosine => Cosine.radius(category),
+            Distance::LogSpectral => LogSpectral.radius(category),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::covariance::Structure;
+    use crate::spectrum::Vector;
+
+    fn concept(values: &[f64], radius: f64) -> Concept {
+        let vector: Vector = values.iter().map(|&v| c64::new(v, 0.0)).collect();
+        Concept::new(0, vector, radius, Structure::Diagonal)
+    }
+
+    #[test]
+    fn test_euclidean() {
+        let a = concept(&[0.0, 0.0], 5.0);
+        let b = concept(&[3.0, 4.0], 1.0);
+        assert!((Euclidean.distance(&a, &b) - 5.0).abs() < 1e-9);
+        assert!((Euclidean.radius(&a) - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mahalanobis() {
+        let a = concept(&[1.0, 1.0, 1.0, 1.0], 6.0);
+        let b = concept(&[7.0, 1.0, 1.0, 1.0], 6.0);
+        let c = concept(&[4.0, 1.0, 1.0, 1.0], 6.0);
+        // The isotropic prior puts the Euclidean radius at the Mahalanobis one
+        assert!((Mahalanobis.distance(&a, &b) - Mahalanobis.radius(&a)).abs() < 1e-9);
+        assert!((Mahalanobis.distance(&a, &c) - Mahalanobis.radius(&a) / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cosine() {
+        let a = concept(&[1.0, 2.0], 1.0);
+        let b = concept(&[2.0, 4.0], 1.0);
+        let c = concept(&[-2.0, 1.0], 1.0);
+        assert!(Cosine.distance(&a, &b).abs() < 1e-9);
+        assert!((Cosine.distance(&a, &c) - 1.0).abs() < 1e-9);
+        let negated = concept(&[-1.0, -2.0], 1.0);
+        assert!((Cosine.distance(&a, &negated) - 2.0).abs() < 1e-9);
+        assert!(Cosine.radius(&a) < 1.0);
+    }
+
+    #[test]
+    fn test_log_spectral() {
+        let a = concept(&[1.0, 2.0], 1.0);
+        let b = concept(&[10.0, 20.0], 1.0);
+        assert!((LogSpectral.distance(&a, &b) - 20.0).abs() < 1e-9);
+        assert!(LogSpectral.distance(&a, &a).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_scaled_edge_is_member() {
+        let a = concept(&[3.0, 4.0], 1.0);
+        let edge = concept(&[3.0 * 1.2, 4.0 * 1.2], 1.0);
+        for metric in &[Distance::Euclidean, Distance::Cosine, Distance::LogSpectral] {
+            assert!(metric.distance(&a, &edge) <= metric.radius(&a) + 1e-9);
+        }
+    }
+}
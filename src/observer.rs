@@ -0,0 +1,78 @@
+use crate::concept_symbol::{Concept, Label};
+use crate::dimension::Segment;
+use crate::segmentation::Information;
+use crate::spectrum::Spectrum;
+
+/// Something that happens while a dimension perceives a spectrum, in the
+/// order listed. Frozen dimensions learn nothing, so never create, update,
+/// merge, split, or forget a concept.
+#[derive(Clone, Copy, Debug)]
+pub enum Event<'a> {
+    /// The dimension received the spectrum from the level below
+    Perceived { spectrum: &'a Spectrum },
+    /// The first concept overlapped the second so much that the second
+    /// absorbed it, along with its symbols and counts
+    Merged { from: Label, into: Label },
+    /// The exemplars of the original concept formed two clusters, the smaller
+    /// of which founded the concept, taking the given share of the counts
+    Split { original: Label, concept: &'a Concept, share: f64 },
+    /// The concept was seen too rarely since the counts last decayed
+    Forgotten { label: Label },
+    /// The concept of the spectrum was assigned to the category, whose label
+    /// carries the given information given the labels before it
+    Categorized { label: Label, information: Information },
+    /// The concept was unlike every category, so founded a new one
+    Created { concept: &'a Concept },
+    /// The category was updated with the concept
+    Updated { concept: &'a Concept },
+    /// The unfinished segment ended and was abstracted to the spectrum that
+    /// the dimension passes to the level above
+    Segmented { segment: &'a Segment, spectrum: &'a Spectrum },
+}
+
+/// Reacts to what happens inside the dimensions as they perceive, e.g. to
+/// plot, log, or evaluate the perception while it runs
+pub trait Observer {
+    /// Reacts to the event
+    ///
+    /// # Arguments
+    /// * `frame` - index of the frame of the source whose perception led to
+    ///   the event, at every level
+    /// * `level` - level of the dimension at which the event happened
+    /// * `event` - what happened
+    ///
+    fn notify(&mut self, frame: usize, level: u16, event: &Event);
+}
+
+/// Ignores every event
+impl Observer for () {
+    fn notify(&mut self, _: usize, _: u16, _: &Event) {}
+}
+
+/// Any closure taking the frame, level, and event observes
+impl<F: FnMut(usize, u16, &Event)> Observer for F {
+    fn notify(&mut self, frame: usize, level: u16, event: &Event) {
+        self(frame, level, event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closure() {
+        let spectrum = Spectrum::frame(ndarray::Array1::zeros(2), 3);
+        let mut frames = Vec::new();
+        {
+            let mut observer = |frame: usize, level: u16, event: &Event| {
+                if let Event::Perceived { spectrum } = event {
+                    frames.push((frame, level, spectrum.frames.start));
+                }
+            };
+            observer.notify(3, 1, &Event::Perceived { spectrum: &spectrum });
+            ().notify(4, 1, &Event::Perceived { spectrum: &spectrum });
+        }
+        assert_eq!(frames, vec![(3, 1, 3)]);
+    }
+}
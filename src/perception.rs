@@ -1,50 +1,274 @@
 use crate::spectrum::Spectrum;
 use crate::dimension::Dimension;
 use crate::config;
+use crate::error::PredictError;
+use crate::observer::{Event, Observer};
+use indicatif::{ProgressBar, ProgressStyle};
+use log::info;
 use ndarray::Array1;
 use ndarray_linalg::types::c64;
+use std::sync::{mpsc, Mutex, PoisonError};
+use std::thread;
 
-/// Generates a 4-level IDyOT memory from the input signal.
+/// Generates an IDyOT memory of `max_depth` levels from the input signal.
+/// The radius of each level is ten times that of the level below it.
+/// If pipelined, the levels perceive concurrently, with the same result.
+/// Fails if the configuration is invalid or a frame is unlike the others.
 ///
 /// # Arguments
 /// * `config` - configuration for scale, resolution, and depth
 /// * `signal` - input signal to process into memory
 ///
-pub fn process(config: &config::Config, signal: Vec<Array1<c64>>) -> Vec<Dimension> {
-    let mut dimensions = vec![
-        Dimension::new(0, 1.0 * config.radius_scale, config.resolution),
-        Dimension::new(1, 10.0 * config.radius_scale, config.resolution),
-        Dimension::new(2, 100.0 * config.radius_scale, config.resolution),
-        Dimension::new(3, 1000.0 * config.radius_scale, config.resolution),
-    ];
+pub fn process(config: &config::Config, signal: Vec<Array1<c64>>) -> Result<Vec<Dimension>, PredictError> {
+    process_with(config, signal, &mut ())
+}
+
+/// Generates an IDyOT memory as `process` does, notifying the observer of
+/// every event inside the dimensions. The events of each level arrive in
+/// order, but if pipelined, those of different levels may interleave.
+///
+/// # Arguments
+/// * `config` - configuration for scale, resolution, and depth
+/// * `signal` - input signal to process into memory
+/// * `observer` - notified of the events of the perception
+///
+pub fn process_with(config: &config::Config, signal: Vec<Array1<c64>>, observer: &mut (dyn Observer + Send)) -> Result<Vec<Dimension>, PredictError> {
+    config.validate()?;
+    let mut dimensions: Vec<Dimension> = (0..config.max_depth)
+        .map(|level| Dimension::new(level, config))
+        .collect();
+
+    let bar = progress(config, signal.len());
+    if config.pipelined {
+        dimensions = pipeline(dimensions, signal, config.channel_capacity, &bar, observer)?;
+    } else {
+        for (i, point) in signal.into_iter().enumerate() {
+            perceive(&mut dimensions, Spectrum::frame(point, i), i, observer)?;
+            bar.inc(1);
+        }
+    }
+    bar.finish();
+    for dimension in dimensions.iter() {
+        let counters = dimension.counters();
+        info!("level {}: {} spectra perceived, {} concepts created, {} segments abstracted",
+              dimension.level(), counters.perceived, counters.concepts, counters.segments);
+    }
+    Ok(dimensions)
+}
 
-    let n = signal.len();
-    for (i, point) in signal.into_iter().enumerate() {
-        perceive(&mut dimensions, point);
-        println!("{}. {:.2}", i, (i as f64 / n as f64) * 100f64);
+/// Returns a bar showing the progress through the frames of the signal, with
+/// the rate of perception and the time remaining, hidden unless configured
+///
+/// # Arguments
+/// * `config` - configuration of whether to show the progress
+/// * `frames` - number of frames in the signal
+///
+fn progress(config: &config::Config, frames: usize) -> ProgressBar {
+    if !config.progress {
+        return ProgressBar::hidden();
     }
-    dimensions
+    let bar = ProgressBar::new(frames as u64);
+    bar.set_style(ProgressStyle::default_bar()
+        .template("{elapsed_precise} [{wide_bar}] {pos}/{len} frames ({per_sec}, ETA {eta})"));
+    // Redrawing for every frame would slow the perception
+    bar.set_draw_delta((frames as u64 / 1000).max(1));
+    bar
+}
+
+/// Perceives the input signal with each dimension on its own thread, which
+/// receives the spectra of the dimension below it through a bounded channel
+/// and sends those it abstracts to the dimension above. Every dimension
+/// perceives the same spectra in the same order as when perceiving serially.
+/// A dimension that fails stops receiving, and the failure of the lowest
+/// such dimension is returned once the others have finished.
+///
+/// # Arguments
+/// * `dimensions` - dimensions of the memory, from the lowest level up
+/// * `signal` - input signal to process into memory
+/// * `capacity` - number of spectra a channel holds before its sender waits
+/// * `bar` - progress through the frames sent to the lowest dimension
+/// * `observer` - notified of the events of the perception, one at a time
+///
+fn pipeline(dimensions: Vec<Dimension>, signal: Vec<Array1<c64>>, capacity: usize, bar: &ProgressBar,
+            observer: &mut (dyn Observer + Send)) -> Result<Vec<Dimension>, PredictError> {
+    let observer = Mutex::new(observer);
+    thread::scope(|scope| {
+        // Each spectrum travels with the index of the frame that led to it
+        let (input, mut inbox) = mpsc::sync_channel::<(usize, Spectrum)>(capacity);
+        let workers: Vec<thread::ScopedJoinHandle<Result<Dimension, PredictError>>> = dimensions.into_iter()
+            .map(|mut dimension| {
+                let (outbox, next) = mpsc::sync_channel(capacity);
+                let received = std::mem::replace(&mut inbox, next);
+                let observer = &observer;
+                scope.spawn(move || {
+                    let mut notify = |frame: usize, level: u16, event: &Event| {
+                        observer.lock().unwrap_or_else(PoisonError::into_inner).notify(frame, level, event)
+                    };
+                    for (frame, spectrum) in received {
+                        if let Some(superior) = dimension.perceive_with(spectrum, frame, &mut notify)? {
+                            // The top dimension has no one to send to
+                            let _ = outbox.send((frame, superior));
+                        }
+                    }
+                    Ok(dimension)
+                })
+            })
+            .collect();
+        drop(inbox);
+
+        for (i, point) in signal.into_iter().enumerate() {
+            // Level 0 only hangs up once it has failed
+            if input.send((i, Spectrum::frame(point, i))).is_err() {
+                break;
+            }
+            bar.inc(1);
+        }
+        drop(input);
+        workers.into_iter()
+            .map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    })
 }
 
 /// Updates all appropriate levels with the given point
 ///
 /// # Arguments
 /// * `dimensions` - dimensions of the memory
-/// * `value` - current value in signal that is added to the dimensions
+/// * `spectrum` - current frame of the signal that is added to the dimensions
+/// * `frame` - index of the current frame
+/// * `observer` - notified of the events of the perception
 ///
-fn perceive(dimensions: &mut Vec<Dimension>, value: Array1<c64>) {
-    let mut spectrum = Spectrum::point(value);
+fn perceive(dimensions: &mut Vec<Dimension>, spectrum: Spectrum, frame: usize, observer: &mut dyn Observer) -> Result<(), PredictError> {
+    let mut spectrum = spectrum;
     for dimension in dimensions.iter_mut() {
-        match dimension.perceive(spectrum) {
+        match dimension.perceive_with(spectrum, frame, observer)? {
             Some(result) => spectrum = result,
             None => break,
         }
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::concept_symbol::Label;
+    use crate::segmentation::Segmentation;
+    use std::collections::BTreeSet;
+    use crate::spectrum::Span;
+
+    #[test]
+    fn test_pipeline() {
+        let mut config = config::Config::default().unwrap();
+        config.resolution = 4;
+        config.max_depth = 3;
+        config.channel_capacity = 1;
+        config.segmenters = vec![Segmentation::FixedLength(4); 3];
+        let signal: Vec<Array1<c64>> = (0..200)
+            .map(|i| Array1::from(vec![c64::new(((i * 7) % 13) as f64, 0.0); 4]))
+            .collect();
+        let serial = process(&config, signal.clone()).unwrap();
+        config.pipelined = true;
+        let pipelined = process(&config, signal).unwrap();
+
+        assert_eq!(pipelined.len(), serial.len());
+        for (a, b) in serial.iter().zip(pipelined.iter()) {
+            let symbols = |dimension: &Dimension| -> Vec<(usize, usize, Span)> {
+                dimension.symbols().map(|(i, symbol)| (i, symbol.label, symbol.frames)).collect()
+            };
+            assert_eq!(a.level(), b.level());
+            assert_eq!(symbols(a), symbols(b));
+            assert_eq!(a.segments(), b.segments());
+            // Only the order of summing over the counts may differ
+            let close = a.information().iter().zip(b.information())
+                .all(|(x, y)| (x.surprisal - y.surprisal).abs() < 1e-9 && (x.entropy - y.entropy).abs() < 1e-9);
+            assert!(close && a.information().len() == b.information().len());
+        }
+        assert!(serial[2].symbols().count() > 0);
+        assert_eq!(pipelined[0].counters(), serial[0].counters());
+        assert_eq!(serial[0].counters().perceived, 200);
+        assert_eq!(serial[1].counters().perceived, serial[0].counters().segments);
+    }
+
+    #[test]
+    fn test_observer() {
+        let mut config = config::Config::default().unwrap();
+        config.resolution = 4;
+        config.max_depth = 2;
+        config.consolidation_period = 10;
+        config.merge_threshold = 0.5;
+        config.forgetting_period = 25;
+        config.prune_below = 2;
+        config.segmenters = vec![Segmentation::FixedLength(3); 2];
+        let signal: Vec<Array1<c64>> = (0..60)
+            .map(|i| Array1::from(vec![c64::new(((i * 5) % 11) as f64, 0.0); 4]))
+            .collect();
+        // Tally the events of each kind at each level, the concepts they leave
+        // each level with, and the frames of level 0
+        let run = |config: &config::Config| {
+            let mut tallies = [[0; 8]; 2];
+            let mut live = [BTreeSet::new(), BTreeSet::new()];
+            let mut frames = Vec::new();
+            let dimensions = process_with(config, signal.clone(), &mut |frame: usize, level: u16, event: &Event| {
+                let kind = match event {
+                    Event::Perceived { .. } => 0,
+                    Event::Categorized { .. } => 1,
+                    Event::Created { .. } => 2,
+                    Event::Updated { .. } => 3,
+                    Event::Segmented { .. } => 4,
+                    Event::Merged { .. } => 5,
+                    Event::Split { .. } => 6,
+                    Event::Forgotten { .. } => 7,
+                };
+                tallies[level as usize][kind] += 1;
+                match event {
+                    Event::Created { concept } | Event::Split { concept, .. } => live[level as usize].insert(concept.label),
+                    Event::Merged { from: label, .. } | Event::Forgotten { label } => live[level as usize].remove(label),
+                    _ => false,
+                };
+                if level == 0 && kind == 0 {
+                    frames.push(frame);
+                }
+            }).unwrap();
+            (dimensions, tallies, live, frames)
+        };
+        let (dimensions, tallies, live, frames) = run(&config);
+        assert_eq!(frames, (0..60).collect::<Vec<usize>>());
+        for (dimension, tally) in dimensions.iter().zip(tallies.iter()) {
+            let counters = dimension.counters();
+            assert_eq!(tally[0], counters.perceived);
+            assert_eq!(tally[1], counters.perceived);
+            assert_eq!(tally[2] + tally[6], counters.concepts);
+            assert_eq!(tally[3], counters.perceived);
+            assert_eq!(tally[4], counters.segments);
+        }
+        // Every concept created or split is known unless merged or forgotten
+        for (dimension, labels) in dimensions.iter().zip(live.iter()) {
+            let concepts: BTreeSet<Label> = dimension.concepts().map(|concept| concept.label).collect();
+            assert_eq!(&concepts, labels);
+        }
+        assert!(tallies[0][5] + tallies[0][7] > 0);
+        assert_eq!(tallies[1][0], tallies[0][4]);
+        assert!(tallies[0][4] > 0);
+        config.pipelined = true;
+        let (_, pipelined, _, frames) = run(&config);
+        assert_eq!((pipelined, frames.len()), (tallies, 60));
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let mut config = config::Config::default().unwrap();
+        config.max_depth = 2;
+        let mut signal: Vec<Array1<c64>> = (0..20)
+            .map(|i| Array1::from(vec![c64::new(i as f64, 0.0); 4]))
+            .collect();
+        signal.push(Array1::from(vec![c64::new(0.0, 0.0); 5]));
+        assert!(matches!(process(&config, signal.clone()), Err(PredictError::Shape(_))));
+        config.pipelined = true;
+        assert!(matches!(process(&config, signal.clone()), Err(PredictError::Shape(_))));
+        config.resolution = 0;
+        assert!(matches!(process(&config, signal), Err(PredictError::Configuration(_))));
+    }
 
     #[test]
     fn test_process() -> Result<(), String> {
@@ -0,0 +1,331 @@
+use crate::covariance::{adjoint, eigh, product};
+use crate::error::PredictError;
+use crate::spectrum::{Signal, Vector};
+use crate::transformation::Transform;
+use ndarray::Array2;
+use ndarray_linalg::types::c64;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use serde::{Serialize, Deserialize};
+use std::borrow::Cow;
+use std::cell::OnceCell;
+
+/// Smallest eigenvalue inverted when undoing a projection
+const FLOOR: f64 = 1e-12;
+
+/// Selects how the coefficients of the trajectories abstracted at a level are
+/// reduced, so that the vectors of the superior level stay tractable. Every
+/// reduction records what it needs to expand a reduced vector again.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Reduction {
+    /// Keep every coefficient
+    #[default]
+    Full,
+    /// Keep the given number of the coarsest rows of the coefficients, i.e.
+    /// the lowest frequencies of every component
+    Truncate(usize),
+    /// Project the coefficients onto the given number of random directions,
+    /// drawn from the seed, which approximately preserves their distances
+    RandomProjection { dimensions: usize, seed: u64 },
+}
+
+impl Reduction {
+    /// Returns the reduced coefficients as a vector
+    ///
+    /// # Arguments
+    /// * `coefficients` - coefficients of a trajectory, one row per sample
+    /// * `transform` - transform by which the coefficients were found
+    ///
+    pub fn reduce(&self, coefficients: &Signal, transform: &dyn Transform) -> Vector {
+        match *self {
+            Reduction::Full => flatten(coefficients),
+            Reduction::Truncate(rows) => {
+                let order = transform.order(coefficients.nrows());
+                let kept = &order[..rows.min(order.len())];
+                let mut reduced = Signal::zeros((kept.len(), coefficients.ncols()));
+                for (i, row) in kept.iter().enumerate() {
+                    reduced.row_mut(i).assign(&coefficients.row(*row));
+                }
+                flatten(&reduced)
+            }
+            Reduction::RandomProjection { dimensions, seed } => {
+                let point = flatten(coefficients);
+                Projection::new(dimensions, point.len(), seed).reduce(&point)
+            }
+        }
+    }
+
+    /// Returns the coefficients of which the vector is the reduction, with
+    /// the discarded coefficients zero or, for a projection, the coefficients
+    /// of least energy that project to the vector. Fails if the vector cannot
+    /// be the reduction of coefficients of the given shape.
+    ///
+    /// # Arguments
+    /// * `point` - reduced coefficients, as returned by `reduce`
+    /// * `shape` - rows and columns of the coefficients before the reduction
+    /// * `transform` - transform by which the coefficients were found
+    ///
+    pub fn expand(&self, point: &Vector, shape: (usize, usize), transform: &dyn Transform) -> Result<Signal, PredictError> {
+        let (rows, columns) = shape;
+        match *self {
+            Reduction::Full => unflatten(point, rows, columns),
+            Reduction::Truncate(kept) => {
+                let reduced = unflatten(point, kept.min(rows), columns)?;
+                let mut coefficients = Signal::zeros(shape);
+                for (i, row) in transform.order(rows).iter().take(reduced.nrows()).enumerate() {
+                    coefficients.row_mut(*row).assign(&reduced.row(i));
+                }
+                Ok(coefficients)
+            }
+            Reduction::RandomProjection { dimensions, seed } => {
+                Projection::new(dimensions, rows * columns, seed).expand(point, rows, columns)
+            }
+        }
+    }
+}
+
+/// Random projection along with its pseudo-inverse, which undoes it with
+/// the coefficients of least energy
+#[derive(Clone, Debug)]
+pub struct Projection {
+    /// Projection from the coefficients, one row per dimension projected onto
+    matrix: Array2<c64>,
+    /// Least-norm solution P^T (P P^T)^-1 of P x = y, one row per coefficient
+    inverse: Array2<c64>,
+}
+
+impl Projection {
+    /// Returns the projection of coefficients of the given length onto the
+    /// given number of random directions, drawn from the seed
+    ///
+    /// # Arguments
+    /// * `dimensions` - number of dimensions to project onto
+    /// * `length` - number of coefficients to project
+    /// * `seed` - seed of the random entries
+    ///
+    pub fn new(dimensions: usize, length: usize, seed: u64) -> Projection {
+        let matrix = projection(dimensions, length, seed);
+        let transpose = matrix.t().to_owned();
+        let (values, vectors) = eigh(&product(&matrix, &transpose));
+        let mut weights = adjoint(&vectors);
+        for (mut row, value) in weights.genrows_mut().into_iter().zip(values.iter()) {
+            row.mapv_inplace(|weight| if *value > FLOOR { weight / value } else { c64::new(0.0, 0.0) });
+        }
+        let inverse = product(&transpose, &product(&vectors, &weights));
+        Projection { matrix, inverse }
+    }
+
+    /// Returns the number of coefficients this projects
+    fn length(&self) -> usize {
+        self.matrix.ncols()
+    }
+
+    /// Returns the projection of the flattened coefficients
+    fn reduce(&self, point: &Vector) -> Vector {
+        project(&self.matrix, point)
+    }
+
+    /// Returns the coefficients of least energy projecting to the vector,
+    /// failing unless it is as long as the projection and the coefficients
+    /// form the given number of rows and columns
+    fn expand(&self, point: &Vector, rows: usize, columns: usize) -> Result<Signal, PredictError> {
+        if point.len() != self.matrix.nrows() {
+            return Err(PredictError::Shape(format!(
+                "projection onto {} dimensions cannot give {} coefficients", self.matrix.nrows(), point.len())));
+        }
+        unflatten(&project(&self.inverse, point), rows, columns)
+    }
+}
+
+/// Reduction that builds its random projection only once, for the length of
+/// the first coefficients it reduces or expands, rather than for each. It is
+/// stored as its reduction, from which the projection is drawn again.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(from = "Reduction", into = "Reduction")]
+pub struct Reducer {
+    reduction: Reduction,
+    projection: OnceCell<Projection>,
+}
+
+impl From<Reduction> for Reducer {
+    fn from(reduction: Reduction) -> Reducer {
+        Reducer { reduction, projection: OnceCell::new() }
+    }
+}
+
+impl From<Reducer> for Reduction {
+    fn from(reducer: Reducer) -> Reduction {
+        reducer.reduction
+    }
+}
+
+impl Reducer {
+    /// Returns the reduced coefficients as a vector, as `Reduction::reduce`
+    /// does
+    ///
+    /// # Arguments
+    /// * `coefficients` - coefficients of a trajectory, one row per sample
+    /// * `transform` - transform by which the coefficients were found
+    ///
+    pub fn reduce(&self, coefficients: &Signal, transform: &dyn Transform) -> Vector {
+        match self.reduction {
+            Reduction::RandomProjection { dimensions, seed } => {
+                let point = flatten(coefficients);
+                self.projection(dimensions, point.len(), seed).reduce(&point)
+            }
+            reduction => reduction.reduce(coefficients, transform),
+        }
+    }
+
+    /// Returns the coefficients of which the vector is the reduction, as
+    /// `Reduction::expand` does
+    ///
+    /// # Arguments
+    /// * `point` - reduced coefficients, as returned by `reduce`
+    /// * `shape` - rows and columns of the coefficients before the reduction
+    /// * `transform` - transform by which the coefficients were found
+    ///
+    pub fn expand(&self, point: &Vector, shape: (usize, usize), transform: &dyn Transform) -> Result<Signal, PredictError> {
+        let (rows, columns) = shape;
+        match self.reduction {
+            Reduction::RandomProjection { dimensions, seed } => {
+                self.projection(dimensions, rows * columns, seed).expand(point, rows, columns)
+            }
+            reduction => reduction.expand(point, shape, transform),
+        }
+    }
+
+    /// Returns the projection of coefficients of the given length, which is
+    /// built afresh unless it is as long as the one built first
+    fn projection(&self, dimensions: usize, length: usize, seed: u64) -> Cow<'_, Projection> {
+        let projection = self.projection.get_or_init(|| Projection::new(dimensions, length, seed));
+        if projection.length() == length {
+            Cow::Borrowed(projection)
+        } else {
+            Cow::Owned(Projection::new(dimensions, length, seed))
+        }
+    }
+}
+
+/// Returns the coefficients row by row as a vector
+fn flatten(coefficients: &Signal) -> Vector {
+    coefficients.iter().copied().collect()
+}
+
+/// Returns the vector as coefficients with the given number of rows and
+/// columns, failing if it has a different number of entries
+fn unflatten(point: &Vector, rows: usize, columns: usize) -> Result<Signal, PredictError> {
+    point.clone().into_shape((rows, columns)).map_err(|_| PredictError::Shape(format!(
+        "{} coefficients cannot form {} rows of {}", point.len(), rows, columns)))
+}
+
+/// Returns the product of the matrix and the vector
+fn project(matrix: &Array2<c64>, vector: &Vector) -> Vector {
+    matrix.genrows().into_iter()
+        .map(|row| row.iter().zip(vector.iter()).map(|(a, x)| a * x).sum())
+        .collect()
+}
+
+/// Returns a random projection with entries of ±1/sqrt(dimensions), drawn
+/// from the seed, such that the same seed always gives the same projection
+///
+/// # Arguments
+/// * `dimensions` - number of dimensions to project onto
+/// * `length` - number of dimensions to project from
+/// * `seed` - seed of the random entries
+///
+fn projection(dimensions: usize, length: usize, seed: u64) -> Array2<c64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let scale = 1.0 / (dimensions as f64).sqrt();
+    Array2::from_shape_fn((dimensions, length), |_| {
+        c64::new(if rng.gen::<bool>() { scale } else { -scale }, 0.0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray_linalg::norm::Norm;
+    use crate::transformation::{Fourier, Transformation};
+
+    fn coefficients() -> Signal {
+        Signal::from_shape_fn((8, 3), |(i, j)| c64::new(i as f64 - j as f64, (i * j) as f64))
+    }
+
+    fn close(a: &Signal, b: &Signal) -> bool {
+        a.dim() == b.dim() && a.iter().zip(b.iter()).all(|(x, y)| (x - y).norm() < 1e-9)
+    }
+
+    #[test]
+    fn test_full() {
+        let original = coefficients();
+        let point = Reduction::Full.reduce(&original, &Fourier);
+        assert_eq!(point.len(), 24);
+        assert!(close(&Reduction::Full.expand(&point, (8, 3), &Fourier).unwrap(), &original));
+        assert!(matches!(Reduction::Full.expand(&point, (8, 4), &Fourier), Err(PredictError::Shape(_))));
+    }
+
+    #[test]
+    fn test_truncate() {
+        let original = coefficients();
+        let truncate = Reduction::Truncate(3);
+        // The constant, lowest positive, and lowest negative frequencies are kept
+        let point = truncate.reduce(&original, &Fourier);
+        assert_eq!(point.len(), 9);
+        let expanded = truncate.expand(&point, (8, 3), &Fourier).unwrap();
+        for row in 0..8 {
+            let kept = [0, 1, 7].contains(&row);
+            let expected = if kept { original.row(row).to_owned() } else { Vector::zeros(3) };
+            assert_eq!(expanded.row(row), expected);
+        }
+        // Truncating a smooth trajectory's cosine coefficients loses little
+        let smooth = Signal::from_shape_fn((16, 1), |(i, _)| c64::new((i as f64 / 5.0).sin(), 0.0));
+        let cosine = Transformation::Cosine;
+        let coefficients = cosine.forward(&smooth);
+        let reduced = Reduction::Truncate(4).reduce(&coefficients, &cosine);
+        let restored = cosine.inverse(&Reduction::Truncate(4).expand(&reduced, (16, 1), &cosine).unwrap());
+        let error = (&restored - &smooth).iter().map(|x| x.norm_sqr()).sum::<f64>().sqrt();
+        assert!(error < 0.1 * smooth.iter().map(|x| x.norm_sqr()).sum::<f64>().sqrt());
+    }
+
+    #[test]
+    fn test_random_projection() {
+        let original = coefficients();
+        let projection = Reduction::RandomProjection { dimensions: 12, seed: 3 };
+        let point = projection.reduce(&original, &Fourier);
+        assert_eq!(point.len(), 12);
+        // The same seed gives the same projection
+        assert_eq!(projection.reduce(&original, &Fourier), point);
+        // The expansion projects back to the same point
+        let expanded = projection.expand(&point, (8, 3), &Fourier).unwrap();
+        assert_eq!(expanded.dim(), (8, 3));
+        assert!((&projection.reduce(&expanded, &Fourier) - &point).norm() < 1e-6);
+        // Distances are roughly preserved
+        let other = original.mapv(|x| x * 2.0);
+        let far = (&projection.reduce(&other, &Fourier) - &point).norm();
+        let actual = flatten(&(&other - &original)).norm();
+        assert!(far > 0.3 * actual && far < 3.0 * actual);
+    }
+
+    #[test]
+    fn test_reducer() {
+        let original = coefficients();
+        let reduction = Reduction::RandomProjection { dimensions: 12, seed: 3 };
+        let reducer = Reducer::from(reduction);
+        // The projection is built once and reduces like the reduction
+        let point = reducer.reduce(&original, &Fourier);
+        assert_eq!(reducer.projection.get().map(Projection::length), Some(24));
+        assert!((&point - &reduction.reduce(&original, &Fourier)).norm() < 1e-12);
+        let expanded = reducer.expand(&point, (8, 3), &Fourier).unwrap();
+        assert!(close(&expanded, &reduction.expand(&point, (8, 3), &Fourier).unwrap()));
+        // Coefficients of another length are projected afresh
+        assert_eq!(reducer.reduce(&Signal::zeros((4, 3)), &Fourier).len(), 12);
+        assert!(reducer.expand(&point, (4, 3), &Fourier).is_ok());
+        assert!(matches!(reducer.expand(&Vector::zeros(5), (8, 3), &Fourier), Err(PredictError::Shape(_))));
+        // Only the reduction is stored
+        let json = serde_json::to_string(&reducer).unwrap();
+        assert_eq!(json, serde_json::to_string(&reduction).unwrap());
+        let restored: Reducer = serde_json::from_str(&json).unwrap();
+        assert_eq!(Reduction::from(restored), reduction);
+    }
+}
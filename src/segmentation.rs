@@ -1,26 +1,273 @@
 use crate::concept_symbol::Label;
-use crate::markov_model::{BigramModel, UnigramModel};
+use crate::markov_model::{NGramModel, Smoothing, UnigramModel};
+use serde::{Serialize, Deserialize};
 
-/// Determines whether to segment at the current position
+/// Number of recent steps whose mean a peak must exceed
+const WINDOW: usize = 16;
+
+/// Information content of a perceived label under the markov model of its
+/// dimension, measured before the model is updated with the label
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Information {
+    /// Bits of surprise at the label given the preceding labels
+    pub surprisal: f64,
+    /// Bits of uncertainty about the label given the preceding labels
+    pub entropy: f64,
+    /// Bits of uncertainty about the label removed by the preceding labels
+    pub gain: f64,
+}
+
+impl Information {
+    /// Returns the information content of the label given the preceding labels
+    ///
+    /// # Arguments
+    /// * `ngram` - markov model of the labels seen so far
+    /// * `context` - preceding labels, most recent last
+    /// * `label` - label to measure
+    /// * `smoothing` - method of estimating the probabilities
+    ///
+    pub fn measure(ngram: &NGramModel<Label>, context: &[Label], label: &Label, smoothing: Smoothing) -> Information {
+        let entropy = ngram.entropy(context, smoothing);
+        Information {
+            surprisal: ngram.surprisal(context, label, smoothing),
+            entropy,
+            gain: ngram.entropy(&[], smoothing) - entropy,
+        }
+    }
+}
+
+/// State of a dimension consulted when deciding whether to segment,
+/// after the markov models have been updated with the current label
+pub struct Context<'a> {
+    /// Unigram model of the labels seen in the dimension
+    pub unigram: &'a UnigramModel<Label>,
+    /// N-gram model of the labels seen in the dimension
+    pub ngram: &'a NGramModel<Label>,
+    /// Label of the symbol before the current symbol
+    pub previous: &'a Label,
+    /// Label of the current symbol
+    pub current: &'a Label,
+    /// Information content of the recent labels, the current one last
+    pub information: &'a [Information],
+    /// Number of symbols in the unfinished segment, excluding the current one
+    pub length: usize,
+}
+
+/// Decides whether the current symbol begins a new segment
+pub trait Segmenter {
+    /// Returns whether to segment before the current symbol
+    ///
+    /// # Arguments
+    /// * `context` - state of the dimension at the current symbol
+    ///
+    fn segment(&self, context: &Context) -> bool;
+}
+
+/// Segments where the current label is rarer than the previous,
+/// i.e. where the information content -log2(count/total) rises
+pub struct UnigramDrop;
+
+impl Segmenter for UnigramDrop {
+    fn segment(&self, context: &Context) -> bool {
+        context.unigram.count(context.previous) > context.unigram.count(context.current)
+    }
+}
+
+/// Segments where the surprisal of the current label given the preceding
+/// ones rises above that of the previous label and above the recent mean
+pub struct SurprisalPeak;
+
+impl Segmenter for SurprisalPeak {
+    fn segment(&self, context: &Context) -> bool {
+        peak(context.information, |information| information.surprisal)
+    }
+}
+
+/// Segments where the uncertainty about the current label, i.e. the entropy
+/// following the preceding labels, rises above that about the previous label
+/// and above the recent mean
+pub struct EntropyRise;
+
+impl Segmenter for EntropyRise {
+    fn segment(&self, context: &Context) -> bool {
+        peak(context.information, |information| information.entropy)
+    }
+}
+
+/// Segments once the unfinished segment has the given number of symbols
+pub struct FixedLength(pub usize);
+
+impl Segmenter for FixedLength {
+    fn segment(&self, context: &Context) -> bool {
+        context.length >= self.0.max(1)
+    }
+}
+
+/// Segments where at least a quorum of its members would segment
+pub struct Hybrid<'a> {
+    /// Segmenters that vote on each boundary
+    pub members: &'a [Segmentation],
+    /// Number of votes needed to segment
+    pub quorum: usize,
+}
+
+impl Segmenter for Hybrid<'_> {
+    fn segment(&self, context: &Context) -> bool {
+        let votes = self.members.iter()
+            .filter(|member| member.segment(context))
+            .count();
+        votes >= self.quorum.max(1)
+    }
+}
+
+/// Selects which segmenter decides the boundaries in a dimension
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub enum Segmentation {
+    #[default]
+    UnigramDrop,
+    SurprisalPeak,
+    EntropyRise,
+    FixedLength(usize),
+    Hybrid { members: Vec<Segmentation>, quorum: usize },
+}
+
+impl Segmenter for Segmentation {
+    fn segment(&self, context: &Context) -> bool {
+        match self {
+            Segmentation::UnigramDrop => UnigramDrop.segment(context),
+            Segmentation::SurprisalPeak => SurprisalPeak.segment(context),
+            Segmentation::EntropyRise => EntropyRise.segment(context),
+            Segmentation::FixedLength(length) => FixedLength(*length).segment(context),
+            Segmentation::Hybrid { members, quorum } =>
+                Hybrid { members, quorum: *quorum }.segment(context),
+        }
+    }
+}
+
+/// Returns whether the measure of the last step exceeds that of the step
+/// before it and the mean over the preceding window
 ///
 /// # Arguments
-/// * `unigram` - unigram model for this sequence
-/// * `previous` - label of the symbol before the current symbol
-/// * `current` - label of the current symbol
+/// * `information` - information content of the recent steps, current last
+/// * `measure` - measure of information to compare
 ///
-pub fn segment(
-    unigram: &UnigramModel<Label>,
-    previous: &Label,
-    current: &Label
-) -> bool {
-    // Equivalent to comparing information content i.e. -log2(count/total)
-    unigram.count(previous) > unigram.count(current)
+fn peak(information: &[Information], measure: fn(&Information) -> f64) -> bool {
+    match information {
+        [.., previous, current] => {
+            let recent = &information[information.len().saturating_sub(WINDOW + 1)..information.len() - 1];
+            let mean = recent.iter().map(measure).sum::<f64>() / recent.len() as f64;
+            measure(current) > measure(previous) && measure(current) > mean
+        }
+        _ => false,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    /// Returns a sequence of words drawn at random, and the positions at which
+    /// each word begins
+    fn words(lexicon: &[&[Label]], count: usize) -> (Vec<Label>, Vec<usize>) {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut sequence = Vec::new();
+        let mut boundaries = Vec::new();
+        for _ in 0..count {
+            boundaries.push(sequence.len());
+            sequence.extend_from_slice(lexicon[rng.gen_range(0, lexicon.len())]);
+        }
+        (sequence, boundaries)
+    }
+
+    /// Returns the positions at which the segmenter segments the sequence,
+    /// updating the models as a dimension would
+    fn run(segmenter: &dyn Segmenter, sequence: &[Label]) -> Vec<usize> {
+        let smoothing = Smoothing::WittenBell;
+        let mut unigram = UnigramModel::new();
+        let mut ngram = NGramModel::new(2);
+        let mut information = Vec::new();
+        let mut boundaries = Vec::new();
+        let mut length = 0;
+        let mut previous = 0;
+        for (position, current) in sequence.iter().enumerate() {
+            let context = ngram.context();
+            information.push(Information::measure(&ngram, context, current, smoothing));
+            unigram.increment(current);
+            ngram.update(current);
+            let context = Context {
+                unigram: &unigram,
+                ngram: &ngram,
+                previous: &previous,
+                current,
+                information: &information,
+                length,
+            };
+            if segmenter.segment(&context) {
+                boundaries.push(position);
+                length = 0;
+            }
+            length += 1;
+            previous = *current;
+        }
+        boundaries
+    }
+
+    /// Returns the precision and recall of the found boundaries from the
+    /// given position onwards
+    fn score(found: &[usize], known: &[usize], from: usize) -> (f64, f64) {
+        let found: Vec<&usize> = found.iter().filter(|&&b| b >= from).collect();
+        let known: Vec<&usize> = known.iter().filter(|&&b| b >= from).collect();
+        let hits = found.iter().filter(|b| known.contains(b)).count() as f64;
+        (hits / found.len() as f64, hits / known.len() as f64)
+    }
+
+    const LEXICON: [&[Label]; 4] = [&[1, 2, 3], &[4, 5, 6], &[7, 8], &[9, 10, 11, 12]];
+
+    #[test]
+    fn test_unigram_drop() {
+        // Each word is a rare label followed by a frequent filler
+        let lexicon: [&[Label]; 3] = [&[1, 9, 9, 9], &[2, 9, 9, 9], &[3, 9, 9, 9]];
+        let (sequence, known) = words(&lexicon, 40);
+        let found = run(&UnigramDrop, &sequence);
+        assert_eq!(score(&found, &known, 8), (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_surprisal_peak() {
+        let (sequence, known) = words(&LEXICON, 200);
+        let found = run(&SurprisalPeak, &sequence);
+        let (precision, recall) = score(&found, &known, 100);
+        assert!(precision > 0.9 && recall > 0.9, "{} {}", precision, recall);
+    }
+
+    #[test]
+    fn test_entropy_rise() {
+        let (sequence, known) = words(&LEXICON, 200);
+        let found = run(&EntropyRise, &sequence);
+        let (precision, recall) = score(&found, &known, 100);
+        assert!(precision > 0.9 && recall > 0.9, "{} {}", precision, recall);
+    }
+
+    #[test]
+    fn test_fixed_length() {
+        let sequence: Vec<Label> = (0..10).collect();
+        assert_eq!(run(&FixedLength(3), &sequence), vec![3, 6, 9]);
+        assert_eq!(run(&Segmentation::FixedLength(0), &sequence).len(), 9);
+    }
 
     #[test]
-    fn test() {}
+    fn test_hybrid() {
+        let lexicon: [&[Label]; 2] = [&[1, 9, 9, 9], &[2, 9, 9, 9]];
+        let (sequence, _) = words(&lexicon, 20);
+        let members = vec![Segmentation::UnigramDrop, Segmentation::FixedLength(2)];
+        let drop = run(&UnigramDrop, &sequence);
+        let union = run(&Hybrid { members: &members, quorum: 1 }, &sequence);
+        let both = run(&Segmentation::Hybrid { members: members.clone(), quorum: 2 }, &sequence);
+        assert!(drop.iter().all(|b| union.contains(b)));
+        assert!(union.len() > drop.len());
+        assert!(both.iter().all(|b| drop.contains(b) && union.contains(b)));
+        assert!(both.len() < union.len());
+    }
 }
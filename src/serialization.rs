@@ -1,4 +1,20 @@
-// TODO: Use serde for serialization
+use crate::dimension::Dimension;
+use crate::error::PredictError;
+use std::fs::File;
+use std::io::BufWriter;
+
+/// Writes the dimensions of the memory as JSON to the given path, from which
+/// `deserialization::load` restores them
+///
+/// # Arguments
+/// * `dimensions` - dimensions of the memory, from the lowest level up
+/// * `path` - path of the file to write to
+///
+pub fn save(dimensions: &[Dimension], path: &str) -> Result<(), PredictError> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(writer, dimensions)?;
+    Ok(())
+}
 
 #[cfg(test)]
 mod tests {
@@ -13,13 +13,66 @@ pub type Vector = Array1<c64>;
 
 pub type Signal = Array2<c64>;
 
+/// Half-open range [start, end) of positions in a sequence
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Span {
+    /// First position in the range
+    pub start: usize,
+    /// Position after the last in the range
+    pub end: usize,
+}
+
+impl Span {
+    /// Returns the range of positions from start up to but excluding end
+    ///
+    /// # Arguments
+    /// * `start` - first position in the range
+    /// * `end` - position after the last in the range
+    ///
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// Number of positions in the range
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Whether the range has no positions
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    /// Returns the smallest range containing both ranges
+    ///
+    /// # Arguments
+    /// * `other` - range to join with this one
+    ///
+    pub fn join(&self, other: &Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Returns the range scaled by the given factor, e.g. frames to samples
+    ///
+    /// # Arguments
+    /// * `factor` - number of finer positions per position
+    ///
+    pub fn scale(&self, factor: usize) -> Span {
+        Span::new(self.start * factor, self.end * factor)
+    }
+}
+
 /// Spectrum of a trajectory and its length from the subordinate layer
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Spectrum {
     /// Complex spectrum
     pub point: Vector,
     /// Length of the subordinate trajectory
     pub length: usize,
+    /// Positions of the subordinate symbols of the trajectory
+    pub span: Span,
+    /// Positions of the frames of the source subtended by the trajectory
+    pub frames: Span,
 }
 
 impl Spectrum {
@@ -28,7 +81,18 @@ impl Spectrum {
     /// # Arguments
     /// * `value` - scalar to wrap in a Spectrum
     pub fn point(point: Array1<c64>) -> Spectrum {
-        Spectrum { point, length: 1, }
+        Spectrum::frame(point, 0)
+    }
+
+    /// Return a spectrum of a single frame of the source
+    ///
+    /// # Arguments
+    /// * `point` - spectrum of the frame
+    /// * `index` - position of the frame in the source
+    ///
+    pub fn frame(point: Array1<c64>, index: usize) -> Spectrum {
+        let span = Span::new(index, index + 1);
+        Spectrum { point, length: 1, span, frames: span }
     }
 }
 /*
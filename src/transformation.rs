@@ -0,0 +1,432 @@
+use crate::covariance::{eigh, product};
+use crate::error::PredictError;
+use crate::fourier::{fft, ifft};
+use crate::spectrum::{Signal, Vector};
+use ndarray::{Array1, Array2, ArrayView1, Axis};
+use ndarray_linalg::types::c64;
+use serde::{Serialize, Deserialize};
+use std::f64::consts::{PI, SQRT_2};
+
+/// Maps the interpolated trajectory of a segment, one row per sample, to the
+/// coefficients from which the superior level perceives it, and back again
+pub trait Transform {
+    /// Returns the coefficients of the signal
+    ///
+    /// # Arguments
+    /// * `signal` - trajectory with one row per sample
+    ///
+    fn forward(&self, signal: &Signal) -> Signal;
+
+    /// Returns the signal of the coefficients
+    ///
+    /// # Arguments
+    /// * `coefficients` - coefficients as returned by `forward`
+    ///
+    fn inverse(&self, coefficients: &Signal) -> Signal;
+
+    /// Returns the rows of the coefficients from the coarsest to the finest,
+    /// i.e. in the order in which to keep them when truncating
+    ///
+    /// # Arguments
+    /// * `rows` - number of rows of the coefficients
+    ///
+    fn order(&self, rows: usize) -> Vec<usize> {
+        (0..rows).collect()
+    }
+
+    /// Returns the number of columns of the coefficients of a signal with the
+    /// given number of columns, or None if the transform cannot accept it
+    ///
+    /// # Arguments
+    /// * `columns` - number of components of each sample of the signal
+    ///
+    fn width(&self, columns: usize) -> Option<usize> {
+        Some(columns)
+    }
+}
+
+/// Discrete Fourier transform of each component along the trajectory
+pub struct Fourier;
+
+impl Transform for Fourier {
+    fn forward(&self, signal: &Signal) -> Signal {
+        columns(signal, fft)
+    }
+
+    fn inverse(&self, coefficients: &Signal) -> Signal {
+        columns(coefficients, ifft)
+    }
+
+    /// Frequencies of increasing magnitude, each positive before its negative
+    fn order(&self, rows: usize) -> Vec<usize> {
+        (0..rows).map(|i| if i % 2 == 1 { i.div_ceil(2) } else { (rows - i / 2) % rows }).collect()
+    }
+}
+
+/// Orthonormal discrete cosine transform (DCT-II) of each component along the
+/// trajectory, which compacts smooth trajectories into few coefficients
+pub struct Cosine;
+
+impl Transform for Cosine {
+    fn forward(&self, signal: &Signal) -> Signal {
+        columns(signal, |column| apply(&cosine(column.len()), column))
+    }
+
+    fn inverse(&self, coefficients: &Signal) -> Signal {
+        columns(coefficients, |column| apply(&cosine(column.len()).reversed_axes(), column))
+    }
+}
+
+/// Orthonormal Haar wavelet transform of each component along the trajectory
+pub struct Haar;
+
+/// Lowpass filter of the Haar wavelet
+const HAAR: [f64; 2] = [1.0 / SQRT_2, 1.0 / SQRT_2];
+
+impl Transform for Haar {
+    fn forward(&self, signal: &Signal) -> Signal {
+        columns(signal, |column| wavelet(column, &HAAR))
+    }
+
+    fn inverse(&self, coefficients: &Signal) -> Signal {
+        columns(coefficients, |column| unwavelet(column, &HAAR))
+    }
+}
+
+/// Orthonormal Daubechies wavelet transform with four taps (D4) of each
+/// component along the trajectory, which is periodically extended
+pub struct Daubechies;
+
+/// Lowpass filter of the Daubechies D4 wavelet
+const DAUBECHIES: [f64; 4] = [
+    0.482_962_913_144_534_1,
+    0.836_516_303_737_807_9,
+    0.224_143_868_042_013_4,
+    -0.129_409_522_551_260_4,
+];
+
+impl Transform for Daubechies {
+    fn forward(&self, signal: &Signal) -> Signal {
+        columns(signal, |column| wavelet(column, &DAUBECHIES))
+    }
+
+    fn inverse(&self, coefficients: &Signal) -> Signal {
+        columns(coefficients, |column| unwavelet(column, &DAUBECHIES))
+    }
+}
+
+/// Projection of each sample onto the principal axes of the samples it was
+/// fitted to, keeping as many coefficients per sample as there are axes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Pca {
+    /// Mean of the samples
+    pub mean: Vector,
+    /// Orthonormal principal axes as columns, most variant first
+    pub basis: Array2<c64>,
+}
+
+impl Pca {
+    /// Returns the projection onto the given number of principal axes of the
+    /// rows of the signals, failing unless there is at least one row and all
+    /// rows are equally wide
+    ///
+    /// # Arguments
+    /// * `signals` - trajectories with one row per sample
+    /// * `components` - number of principal axes to keep
+    ///
+    pub fn fit(signals: &[Signal], components: usize) -> Result<Pca, PredictError> {
+        let rows: Vec<ArrayView1<c64>> = signals.iter().flat_map(|signal| signal.genrows()).collect();
+        let width = rows.first()
+            .ok_or_else(|| PredictError::Shape("no samples to fit principal axes to".to_string()))?
+            .len();
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(PredictError::Shape("samples of different widths".to_string()));
+        }
+        let mut mean = Vector::zeros(width);
+        for row in rows.iter() {
+            mean += row;
+        }
+        let mean = mean / c64::new(rows.len() as f64, 0.0);
+        let mut scatter = Array2::<c64>::zeros((width, width));
+        for row in rows.iter() {
+            let deviation = row - &mean;
+            for ((i, j), entry) in scatter.indexed_iter_mut() {
+                *entry += deviation[i] * deviation[j].conj();
+            }
+        }
+        let (_, vectors) = eigh(&scatter);
+        let basis = vectors.slice_axis(Axis(1), (0..components.min(width)).into()).to_owned();
+        Ok(Pca { mean, basis })
+    }
+}
+
+impl Transform for Pca {
+    fn forward(&self, signal: &Signal) -> Signal {
+        let centered = signal - &self.mean;
+        product(&centered, &self.basis.mapv(|v| v.conj()))
+    }
+
+    fn inverse(&self, coefficients: &Signal) -> Signal {
+        product(coefficients, &self.basis.t().to_owned()) + &self.mean
+    }
+
+    /// As many columns as principal axes, for samples as wide as the mean
+    fn width(&self, columns: usize) -> Option<usize> {
+        if columns == self.mean.len() { Some(self.basis.ncols()) } else { None }
+    }
+}
+
+/// Selects the transform from a trajectory to the coefficients perceived by
+/// the superior level, all of which but the projection are lossless
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub enum Transformation {
+    #[default]
+    Fourier,
+    Cosine,
+    Haar,
+    Daubechies,
+    Pca(Pca),
+    /// Projection onto the given number of principal axes of the samples of
+    /// the first trajectories abstracted at the level, which only fit it.
+    /// Accepts no signal until it is fitted and becomes `Pca`.
+    LearnedPca { components: usize, trajectories: usize },
+}
+
+impl Transform for Transformation {
+    fn forward(&self, signal: &Signal) -> Signal {
+        match self {
+            Transformation::Fourier => Fourier.forward(signal),
+            Transformation::Cosine => Cosine.forward(signal),
+            Transformation::Haar => Haar.forward(signal),
+            Transformation::Daubechies => Daubechies.forward(signal),
+            Transformation::Pca(pca) => pca.forward(signal),
+            Transformation::LearnedPca { .. } => signal.clone(),
+        }
+    }
+
+    fn inverse(&self, coefficients: &Signal) -> Signal {
+        match self {
+            Transformation::Fourier => Fourier.inverse(coefficients),
+            Transformation::Cosine => Cosine.inverse(coefficients),
+            Transformation::Haar => Haar.inverse(coefficients),
+            Transformation::Daubechies => Daubechies.inverse(coefficients),
+            Transformation::Pca(pca) => pca.inverse(coefficients),
+            Transformation::LearnedPca { .. } => coefficients.clone(),
+        }
+    }
+
+    fn order(&self, rows: usize) -> Vec<usize> {
+        match self {
+            Transformation::Fourier => Fourier.order(rows),
+            Transformation::Cosine => Cosine.order(rows),
+            Transformation::Haar => Haar.order(rows),
+            Transformation::Daubechies => Daubechies.order(rows),
+            Transformation::Pca(pca) => pca.order(rows),
+            Transformation::LearnedPca { .. } => (0..rows).collect(),
+        }
+    }
+
+    fn width(&self, columns: usize) -> Option<usize> {
+        match self {
+            Transformation::Pca(pca) => pca.width(columns),
+            Transformation::LearnedPca { .. } => None,
+            _ => Some(columns),
+        }
+    }
+}
+
+/// Returns the signal with the transform applied to each of its columns,
+/// i.e. to each component along the trajectory
+///
+/// # Arguments
+/// * `signal` - trajectory with one row per sample
+/// * `transform` - transform of a column, preserving its length
+///
+fn columns(signal: &Signal, transform: impl Fn(ArrayView1<c64>) -> Array1<c64>) -> Signal {
+    let mut result = Signal::zeros(signal.raw_dim());
+    for (column, mut output) in signal.gencolumns().into_iter().zip(result.gencolumns_mut()) {
+        output.assign(&transform(column));
+    }
+    result
+}
+
+/// Returns the orthonormal DCT-II matrix of the given size
+fn cosine(n: usize) -> Array2<c64> {
+    Array2::from_shape_fn((n, n), |(k, i)| {
+        let scale = if k == 0 { (1.0 / n as f64).sqrt() } else { (2.0 / n as f64).sqrt() };
+        c64::new(scale * (PI * (i as f64 + 0.5) * k as f64 / n as f64).cos(), 0.0)
+    })
+}
+
+/// Returns the product of the matrix and the column
+fn apply(matrix: &Array2<c64>, column: ArrayView1<c64>) -> Array1<c64> {
+    matrix.genrows().into_iter()
+        .map(|row| row.iter().zip(column.iter()).map(|(a, x)| a * x).sum())
+        .collect()
+}
+
+/// Returns the lengths of the approximations at each level of a periodic
+/// wavelet transform, longest first, halving while the length is even and
+/// at least that of the filter
+fn levels(n: usize, taps: usize) -> Vec<usize> {
+    let mut lengths = Vec::new();
+    let mut length = n;
+    while length >= taps && length % 2 == 0 {
+        lengths.push(length);
+        length /= 2;
+    }
+    lengths
+}
+
+/// Returns the periodic multilevel wavelet transform of the column, with the
+/// coarsest approximation first and the details of finer levels after it
+///
+/// # Arguments
+/// * `column` - column to transform
+/// * `lowpass` - lowpass filter of an orthonormal wavelet
+///
+fn wavelet(column: ArrayView1<c64>, lowpass: &[f64]) -> Array1<c64> {
+    let mut result = column.to_owned();
+    for length in levels(column.len(), lowpass.len()) {
+        let input = result.slice(ndarray::s![..length]).to_owned();
+        let half = length / 2;
+        for i in 0..half {
+            let (mut approximation, mut detail) = (c64::new(0.0, 0.0), c64::new(0.0, 0.0));
+            for (k, &h) in lowpass.iter().enumerate() {
+                let x = input[(2 * i + k) % length];
+                approximation += x * h;
+                detail += x * highpass(lowpass, k);
+            }
+            result[i] = approximation;
+            result[half + i] = detail;
+        }
+    }
+    result
+}
+
+/// Returns the column of which the given coefficients are the wavelet transform
+///
+/// # Arguments
+/// * `coefficients` - coefficients as returned by `wavelet`
+/// * `lowpass` - lowpass filter of the same orthonormal wavelet
+///
+fn unwavelet(coefficients: ArrayView1<c64>, lowpass: &[f64]) -> Array1<c64> {
+    let mut result = coefficients.to_owned();
+    for length in levels(coefficients.len(), lowpass.len()).into_iter().rev() {
+        let input = result.slice(ndarray::s![..length]).to_owned();
+        let half = length / 2;
+        let mut output = Array1::<c64>::zeros(length);
+        for i in 0..half {
+            for (k, &h) in lowpass.iter().enumerate() {
+                output[(2 * i + k) % length] += input[i] * h + input[half + i] * highpass(lowpass, k);
+            }
+        }
+        result.slice_mut(ndarray::s![..length]).assign(&output);
+    }
+    result
+}
+
+/// Returns the tap of the highpass filter that is the quadrature mirror of
+/// the lowpass filter
+fn highpass(lowpass: &[f64], k: usize) -> f64 {
+    let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+    sign * lowpass[lowpass.len() - 1 - k]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(rows: usize) -> Signal {
+        Signal::from_shape_fn((rows, 3), |(i, j)| c64::new((i * (j + 1)) as f64, (i + j) as f64 % 3.0))
+    }
+
+    fn close(a: &Signal, b: &Signal) -> bool {
+        a.dim() == b.dim() && a.iter().zip(b.iter()).all(|(x, y)| (x - y).norm() < 1e-9)
+    }
+
+    fn energy(signal: &Signal) -> f64 {
+        signal.iter().map(|x| x.norm_sqr()).sum()
+    }
+
+    #[test]
+    fn test_inverse() {
+        let transformations = [
+            Transformation::Fourier,
+            Transformation::Cosine,
+            Transformation::Haar,
+            Transformation::Daubechies,
+        ];
+        for transformation in transformations.iter() {
+            for rows in &[1, 6, 8, 16] {
+                let original = signal(*rows);
+                let coefficients = transformation.forward(&original);
+                assert_eq!(coefficients.dim(), original.dim());
+                assert!(close(&transformation.inverse(&coefficients), &original), "{:?}", transformation);
+            }
+        }
+    }
+
+    #[test]
+    fn test_orthonormal() {
+        // The cosine and wavelet transforms preserve the energy of the signal
+        let original = signal(16);
+        for transformation in &[Transformation::Cosine, Transformation::Haar, Transformation::Daubechies] {
+            let coefficients = transformation.forward(&original);
+            assert!((energy(&coefficients) - energy(&original)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_compaction() {
+        // A constant trajectory has only the first coefficient of each column
+        let constant = Signal::from_elem((8, 2), c64::new(2.0, 0.0));
+        for transformation in &[Transformation::Fourier, Transformation::Cosine, Transformation::Haar] {
+            let coefficients = transformation.forward(&constant);
+            let rest = coefficients.slice(ndarray::s![1.., ..]);
+            assert!(rest.iter().all(|x| x.norm() < 1e-9), "{:?}", transformation);
+        }
+        let haar = Haar.forward(&Signal::from_shape_fn((2, 1), |(i, _)| c64::new(i as f64, 0.0)));
+        assert!(close(&haar, &Signal::from_shape_fn((2, 1), |(i, _)| c64::new(if i == 0 { 1.0 } else { -1.0 } / SQRT_2, 0.0))));
+    }
+
+    #[test]
+    fn test_order() {
+        assert_eq!(Fourier.order(6), vec![0, 1, 5, 2, 4, 3]);
+        assert_eq!(Fourier.order(1), vec![0]);
+        assert_eq!(Transformation::Haar.order(4), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_pca() {
+        // Samples along a line are captured exactly by a single axis
+        let line = Signal::from_shape_fn((6, 3), |(i, j)| c64::new(1.0 + i as f64 * (j as f64 - 1.0), 0.0));
+        let pca = Pca::fit(std::slice::from_ref(&line), 1).unwrap();
+        assert_eq!(pca.width(3), Some(1));
+        assert_eq!(pca.width(4), None);
+        assert!(matches!(Pca::fit(&[], 1), Err(PredictError::Shape(_))));
+        let coefficients = pca.forward(&line);
+        assert_eq!(coefficients.dim(), (6, 1));
+        assert!(close(&pca.inverse(&coefficients), &line));
+        let transformation = Transformation::Pca(pca);
+        assert!(close(&transformation.inverse(&transformation.forward(&line)), &line));
+        // A projection yet to be fitted accepts nothing
+        assert_eq!(Transformation::LearnedPca { components: 1, trajectories: 2 }.width(3), None);
+    }
+
+    #[test]
+    fn test_fourier() {
+        // Each component is transformed along the trajectory, not each sample
+        // across the components, with the twiddle factors of the DFT
+        let signal = Signal::from_shape_fn((4, 2), |(i, j)| {
+            c64::new(if j == 0 { 1.0 } else { [0.0, 1.0, 0.0, -1.0][i] }, 0.0)
+        });
+        let expected = Signal::from_shape_vec((4, 2), vec![
+            c64::new(4.0, 0.0), c64::new(0.0, 0.0),
+            c64::new(0.0, 0.0), c64::new(0.0, -2.0),
+            c64::new(0.0, 0.0), c64::new(0.0, 0.0),
+            c64::new(0.0, 0.0), c64::new(0.0, 2.0),
+        ]).unwrap();
+        assert!(close(&Transformation::Fourier.forward(&signal), &expected));
+    }
+}
@@ -1,16 +1,28 @@
 use num::complex::Complex64;
+use plotters::drawing::DrawingAreaErrorKind;
 use plotters::prelude::*;
 use rand;
 use rand::Rng;
 use itertools_num;
 use itertools_num::ItertoolsNum;
+use crate::segmentation::Information;
+use crate::error::PredictError;
+use std::io;
 
 /// Type aliases for improved readability
-type Res = Result<(), Box<dyn std::error::Error>>;
+type Res = Result<(), PredictError>;
 type X = usize;
 type Y = usize;
 type Index = usize;
 type Length = usize;
+type Measure = fn(&Information) -> f64;
+
+/// Failures to draw a plot are failures to write its image
+impl<E: std::error::Error + Send + Sync> From<DrawingAreaErrorKind<E>> for PredictError {
+    fn from(error: DrawingAreaErrorKind<E>) -> PredictError {
+        PredictError::Io(io::Error::other(error.to_string()))
+    }
+}
 
 /// Plot the points on a standard xy-coordinate plot.
 ///
@@ -20,8 +32,9 @@ type Length = usize;
 pub fn plot_scatter(points: Vec<(X, Y)>) -> Res {
     let root = BitMapBackend::new("target/plots/scatter.png", (1024, 1024)).into_drawing_area();
     root.fill(&WHITE)?;
-    let max_x = points.iter().map(|(x, _)| x).max().unwrap();
-    let max_y = points.iter().map(|(_, y)| y).max().unwrap();
+    let nothing = || PredictError::Shape("no points to plot".to_string());
+    let max_x = points.iter().map(|(x, _)| x).max().ok_or_else(nothing)?;
+    let max_y = points.iter().map(|(_, y)| y).max().ok_or_else(nothing)?;
     let mut chart = ChartBuilder::on(&root)
         .x_label_area_size(30)
         .y_label_area_size(30)
@@ -101,7 +114,8 @@ pub fn plot_similarity(matrix: Vec<Vec<f64>>) -> Res {
 pub fn plot_spectrum(stft: Vec<Vec<Complex64>>) -> Res {
     let root = BitMapBackend::new("target/plots/spectrum.png", (1024, 768)).into_drawing_area();
     root.fill(&WHITE)?;
-    let (length, height) = (stft.len(), stft.first().unwrap().len() / 2);
+    let first = stft.first().ok_or_else(|| PredictError::Shape("no frames to plot".to_string()))?;
+    let (length, height) = (stft.len(), first.len() / 2);
     let mut chart = ChartBuilder::on(&root)
         .build_ranged(0..length as i32, height as i32..0)?;
     chart.draw_series(
@@ -119,6 +133,46 @@ pub fn plot_spectrum(stft: Vec<Vec<Complex64>>) -> Res {
     Ok(())
 }
 
+/// Plot the surprisal (red), entropy (blue), and information gain (green)
+/// of each perceived label in a dimension as points over time.
+///
+/// # Arguments
+/// * `information` - information content of each label, oldest first
+///
+pub fn plot_information(information: &[Information]) -> Res {
+    std::fs::create_dir_all("target/plots")?;
+    let root = BitMapBackend::new("target/plots/information.png", (1024, 512)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let n = information.len().max(1) as i32;
+    let max = information.iter()
+        .flat_map(|i| vec![i.surprisal, i.entropy, i.gain])
+        .filter(|v| v.is_finite())
+        .fold(1.0, f64::max);
+    let min = information.iter().map(|i| i.gain).fold(0.0, f64::min);
+    let mut chart = ChartBuilder::on(&root)
+        .x_label_area_size(30)
+        .y_label_area_size(30)
+        .build_ranged(0..n, min..max)?;
+    chart
+        .configure_mesh()
+        .x_labels(5)
+        .y_labels(5)
+        .draw()?;
+    let series: [(Measure, &RGBColor); 3] = [
+        (|i| i.surprisal, &RED),
+        (|i| i.entropy, &BLUE),
+        (|i| i.gain, &GREEN),
+    ];
+    for (measure, color) in series.iter() {
+        chart.draw_series(
+            PointSeries::of_element(
+                information.iter().enumerate().map(|(x, i)| (x as i32, measure(i).min(max))),
+                2, *color, &|c, s, st| EmptyElement::at(c) + Circle::new((0, 0), s, st.filled()),
+            ))?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,6 +213,19 @@ mod tests {
         plot_flow(flow)
     }
 
+    #[test]
+    fn test_plot_information() -> Res {
+        let mut rng = rand::thread_rng();
+        let information: Vec<Information> = (0..100)
+            .map(|_| Information {
+                surprisal: rng.gen_range(0.0, 8.0),
+                entropy: rng.gen_range(0.0, 4.0),
+                gain: rng.gen_range(-1.0, 2.0),
+            })
+            .collect();
+        plot_information(&information)
+    }
+
     #[test]
     fn test_plot_scatter() -> Res {
         let mut rng = rand::thread_rng();